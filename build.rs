@@ -0,0 +1,7 @@
+#[cfg(feature = "tauri")]
+const COMMANDS: &[&str] = &["connect", "respond", "call"];
+
+fn main() {
+    #[cfg(feature = "tauri")]
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,273 @@
+//! Procedural macros for `frontbridge`. Use them through the re-exports in that crate.
+
+use heck::ToLowerCamelCase;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, FnArg, Ident, ItemTrait, LitStr, Pat, ReturnType, Signature, TraitItem, TraitItemFn,
+    Type, parse_macro_input, spanned::Spanned,
+};
+
+/// Turns a trait describing frontend methods into a client that invokes them.
+///
+/// Every method must be `async fn name(&self, ...)`. The trait is emitted with each method
+/// returning `Result<Output, FrontInvokeError>` from a `Send` future, and
+/// `MainUiClient<R: Runtime = Wry>` implements it for `trait MainUi`. A call sends the
+/// arguments as an object keyed by their camelCase names to the camelCase method name
+/// (override with `#[frontbridge(rename = "...")]`). Other attributes on a method are kept on
+/// the trait; the client's impl keeps `cfg` and lint attributes.
+///
+/// ```ignore
+/// #[frontbridge::interface]
+/// pub trait MainUi {
+///     async fn confirm(&self, title: String) -> bool;
+///     #[frontbridge(rename = "toast.show")]
+///     async fn show_toast(&self, text: String, duration_ms: u64);
+/// }
+///
+/// let ui = MainUiClient::new(app.handle());
+/// if ui.confirm("Delete?".into()).await? { ... }
+/// ```
+///
+/// Code that takes `impl MainUi` can be handed a hand-written fake instead of the client.
+#[proc_macro_attribute]
+pub fn interface(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            TokenStream2::from(attr).span(),
+            "`interface` takes no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let item = parse_macro_input!(item as ItemTrait);
+    expand(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(item: ItemTrait) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() || item.generics.where_clause.is_some() {
+        return Err(syn::Error::new(
+            item.generics.span(),
+            "interface traits can't be generic",
+        ));
+    }
+    if let Some(colon) = &item.colon_token {
+        return Err(syn::Error::new(
+            colon.span(),
+            "interface traits can't have supertraits",
+        ));
+    }
+    if let Some(unsafety) = &item.unsafety {
+        return Err(syn::Error::new(
+            unsafety.span(),
+            "interface traits can't be unsafe",
+        ));
+    }
+    let vis = &item.vis;
+    let attrs = &item.attrs;
+    let cfgs = filter_attrs(&item.attrs, &["cfg"]);
+    let ident = &item.ident;
+    let client = format_ident!("{}Client", ident);
+    let client_doc = format!(" Calls the frontend methods of [`{ident}`] through a `FrontBridge`.");
+    let (decls, impls) = item
+        .items
+        .iter()
+        .map(|trait_item| match trait_item {
+            TraitItem::Fn(method) => expand_method(method),
+            other => Err(syn::Error::new(
+                other.span(),
+                "interface traits may only contain methods",
+            )),
+        })
+        .collect::<syn::Result<(Vec<_>, Vec<_>)>>()?;
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis trait #ident {
+            #(#decls)*
+        }
+
+        #(#cfgs)*
+        #[doc = #client_doc]
+        #vis struct #client<R: ::frontbridge::__private::tauri::Runtime = ::frontbridge::__private::tauri::Wry> {
+            bridge: ::frontbridge::FrontBridge<R>,
+            options: ::frontbridge::InvokeOptions,
+        }
+
+        #(#cfgs)*
+        impl<R: ::frontbridge::__private::tauri::Runtime> ::std::clone::Clone for #client<R> {
+            fn clone(&self) -> Self {
+                Self {
+                    bridge: self.bridge.clone(),
+                    options: self.options.clone(),
+                }
+            }
+        }
+
+        #(#cfgs)*
+        impl<R: ::frontbridge::__private::tauri::Runtime> #client<R> {
+            /// Creates a client on the bridge managed by `app_handle`.
+            #vis fn new(app_handle: &::frontbridge::__private::tauri::AppHandle<R>) -> Self {
+                Self::from_bridge(::frontbridge::FrontBridge::init(app_handle))
+            }
+
+            #vis fn from_bridge(bridge: ::frontbridge::FrontBridge<R>) -> Self {
+                Self {
+                    bridge,
+                    options: ::std::default::Default::default(),
+                }
+            }
+
+            /// Uses `options` for every call made through this client.
+            #vis fn with_options(mut self, options: ::frontbridge::InvokeOptions) -> Self {
+                self.options = options;
+                self
+            }
+
+            #vis fn bridge(&self) -> &::frontbridge::FrontBridge<R> {
+                &self.bridge
+            }
+        }
+
+        #(#cfgs)*
+        impl<R: ::frontbridge::__private::tauri::Runtime> #ident for #client<R> {
+            #(#impls)*
+        }
+    })
+}
+
+/// The trait's declaration of `method` and the client's implementation of it.
+fn expand_method(method: &TraitItemFn) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let sig = &method.sig;
+    check_signature(sig)?;
+    if let Some(default) = &method.default {
+        return Err(syn::Error::new(
+            default.span(),
+            "interface methods can't have a body",
+        ));
+    }
+    let name = method_name(sig, &method.attrs)?;
+    let attrs = method
+        .attrs
+        .iter()
+        .filter(|attr| !attr.path().is_ident("frontbridge"));
+    // `deprecated` and the like are errors on impl items; they belong on the trait.
+    let impl_attrs = filter_attrs(
+        &method.attrs,
+        &["cfg", "doc", "allow", "expect", "warn", "deny", "forbid"],
+    );
+    let ident = &sig.ident;
+    let (args, types) = arguments(sig)?;
+    let keys = args.iter().map(|arg| arg.to_string().to_lower_camel_case());
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+
+    let result = quote!(::std::result::Result<#output, ::frontbridge::FrontInvokeError>);
+
+    let decl = quote! {
+        #(#attrs)*
+        fn #ident(&self, #(#args: #types),*)
+            -> impl ::std::future::Future<Output = #result> + ::std::marker::Send;
+    };
+    let imp = quote! {
+        #(#impl_attrs)*
+        async fn #ident(&self, #(#args: #types),*) -> #result {
+            let mut __params = ::frontbridge::__private::serde_json::Map::new();
+            #(
+                __params.insert(
+                    ::std::string::ToString::to_string(#keys),
+                    ::frontbridge::__private::to_param(#name, #keys, &#args)?,
+                );
+            )*
+            self.bridge
+                .invoke_by_name_with(#name, __params, ::std::clone::Clone::clone(&self.options))
+                .await
+        }
+    };
+    Ok((decl, imp))
+}
+
+fn check_signature(sig: &Signature) -> syn::Result<()> {
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span,
+            "interface methods must be `async fn`",
+        ));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(syn::Error::new(
+            sig.generics.span(),
+            "interface methods can't be generic",
+        ));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new(
+            variadic.span(),
+            "interface methods can't be variadic",
+        ));
+    }
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_some() && receiver.mutability.is_none() =>
+        {
+            Ok(())
+        }
+        _ => Err(syn::Error::new(
+            sig.ident.span(),
+            "interface methods must take `&self`",
+        )),
+    }
+}
+
+fn arguments(sig: &Signature) -> syn::Result<(Vec<&Ident>, Vec<&Type>)> {
+    sig.inputs
+        .iter()
+        .skip(1)
+        .map(|arg| match arg {
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                    Ok((&pat.ident, &*arg.ty))
+                }
+                other => Err(syn::Error::new(
+                    other.span(),
+                    "interface arguments must be plain identifiers",
+                )),
+            },
+            FnArg::Receiver(receiver) => {
+                Err(syn::Error::new(receiver.span(), "unexpected receiver"))
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()
+        .map(|args| args.into_iter().unzip())
+}
+
+/// The frontend method name: `#[frontbridge(rename = "...")]` or the camelCase fn name.
+fn method_name(sig: &Signature, attrs: &[Attribute]) -> syn::Result<String> {
+    let mut name = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("frontbridge"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(name.unwrap_or_else(|| sig.ident.to_string().to_lower_camel_case()))
+}
+
+fn filter_attrs<'a>(attrs: &'a [Attribute], names: &[&str]) -> Vec<&'a Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| names.iter().any(|name| attr.path().is_ident(name)))
+        .collect()
+}
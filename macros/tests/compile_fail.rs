@@ -0,0 +1,4 @@
+#[test]
+fn interface_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
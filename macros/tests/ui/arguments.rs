@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface(client = "Ui")]
+trait MainUi {
+    async fn confirm(&self, title: String) -> bool;
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    type Title;
+    async fn confirm(&self, title: String) -> bool;
+}
+
+fn main() {}
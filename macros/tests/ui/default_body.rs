@@ -0,0 +1,10 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    async fn confirm(&self, title: String) -> bool {
+        true
+    }
+}
+
+fn main() {}
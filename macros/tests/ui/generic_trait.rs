@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi<T> {
+    async fn confirm(&self, title: T) -> bool;
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    async fn confirm(&mut self, title: String) -> bool;
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    fn confirm(&self, title: String) -> bool;
+}
+
+fn main() {}
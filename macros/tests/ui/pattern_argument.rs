@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    async fn resize(&self, (width, height): (u32, u32));
+}
+
+fn main() {}
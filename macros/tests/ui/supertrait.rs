@@ -0,0 +1,8 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi: Clone {
+    async fn confirm(&self, title: String) -> bool;
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use frontbridge_macros::interface;
+
+#[interface]
+trait MainUi {
+    #[frontbridge(name = "ui.confirm")]
+    async fn confirm(&self, title: String) -> bool;
+}
+
+fn main() {}
@@ -0,0 +1,1996 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use futures_util::stream::{self, FuturesUnordered, Stream, StreamExt};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tauri::{
+    AppHandle, EventTarget, Manager, Runtime, Webview, WebviewWindow, Window, WindowEvent, Wry,
+    webview::{PageLoadEvent, PageLoadPayload},
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    CANCEL_EVENT, CREDIT_EVENT, CancellationToken, EventTransport, FrontCall, FrontError,
+    FrontInvokeCancel, FrontInvokeCancelled, FrontInvokeCredit, FrontInvokeError,
+    FrontInvokeRequest, FrontInvokeResponse, FrontInvokeTimeout, FrontMethod, FrontendGone,
+    FrontendGoneReason, FrontendNotReady, HandlerRegistry, Inbound, PendingTable, ProgressCallback,
+    READY_EVENT, REPLY_EVENT, REQUEST_EVENT, RESPONSE_EVENT, RejectReason, RejectedResponse,
+    Settlement, StreamOverflow, Transport,
+};
+
+const MAIN_LABEL: &str = "main";
+pub const READY_QUEUE_CAPACITY: usize = 64;
+/// Chunks a streaming call buffers, and so the credit its frontend starts with.
+pub const STREAM_CAPACITY: usize = 16;
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+type SecurityHook = Arc<dyn Fn(&RejectedResponse) + Send + Sync>;
+
+fn report_rejected(hook: Option<&SecurityHook>, rejected: RejectedResponse) {
+    match hook {
+        Some(hook) => hook(&rejected),
+        None => log::warn!(
+            "[frontbridge] rejected response id={} from {}: {}",
+            rejected.id,
+            rejected.origin.as_deref().unwrap_or("unknown webview"),
+            rejected.reason
+        ),
+    }
+}
+
+/// Per-bridge settings, filled in by the plugin `Builder`.
+#[derive(Clone)]
+pub(crate) struct BridgeConfig {
+    pub(crate) request_event: String,
+    pub(crate) response_event: String,
+    pub(crate) cancel_event: String,
+    pub(crate) credit_event: String,
+    pub(crate) ready_event: String,
+    pub(crate) reply_event: String,
+    pub(crate) default_target: InvokeTarget,
+    /// `None` waits forever.
+    pub(crate) default_timeout: Option<Duration>,
+    pub(crate) ready_timeout: Duration,
+    /// Holds requests to a labelled webview until it sends `READY_EVENT`. Off unless asked
+    /// for, so frontends that never announce themselves keep working.
+    pub(crate) wait_for_ready: bool,
+    pub(crate) ready_queue_capacity: usize,
+    pub(crate) stream_capacity: usize,
+    pub(crate) log_traffic: bool,
+    pub(crate) security_hook: Option<SecurityHook>,
+    /// Replaces the default `EventTransport` built from the event names above.
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            request_event: REQUEST_EVENT.to_string(),
+            response_event: RESPONSE_EVENT.to_string(),
+            cancel_event: CANCEL_EVENT.to_string(),
+            credit_event: CREDIT_EVENT.to_string(),
+            ready_event: READY_EVENT.to_string(),
+            reply_event: REPLY_EVENT.to_string(),
+            default_target: InvokeTarget::default(),
+            default_timeout: Some(DEFAULT_TIMEOUT),
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+            wait_for_ready: false,
+            ready_queue_capacity: READY_QUEUE_CAPACITY,
+            stream_capacity: STREAM_CAPACITY,
+            log_traffic: false,
+            security_hook: None,
+            transport: None,
+        }
+    }
+}
+
+/// Which webview(s) a request is delivered to.
+#[derive(Debug, Clone, Default)]
+pub enum InvokeTarget {
+    /// The `main` window, or every webview when there is no `main` window.
+    #[default]
+    Main,
+    /// The webview window with this label; fails if it doesn't exist.
+    Window(String),
+    /// The webview with this label, e.g. a child webview inside a multi-webview window.
+    Webview(String),
+    /// An arbitrary event target. Labelled targets are gated on readiness like windows.
+    Event(EventTarget),
+    /// Whichever webview window currently has focus.
+    Focused,
+    /// Every webview; the first reply wins.
+    Any,
+}
+
+impl InvokeTarget {
+    pub fn window(label: impl Into<String>) -> Self {
+        Self::Window(label.into())
+    }
+
+    pub fn webview(label: impl Into<String>) -> Self {
+        Self::Webview(label.into())
+    }
+}
+
+impl From<EventTarget> for InvokeTarget {
+    fn from(target: EventTarget) -> Self {
+        Self::Event(target)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InvokeOptions {
+    timeout: Option<Option<Duration>>,
+    cancel: Option<CancellationToken>,
+    target: Option<InvokeTarget>,
+    progress: Option<ProgressCallback>,
+}
+
+impl std::fmt::Debug for InvokeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InvokeOptions")
+            .field("timeout", &self.timeout)
+            .field("cancel", &self.cancel)
+            .field("target", &self.target)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl InvokeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(Some(timeout));
+        self
+    }
+
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = Some(None);
+        self
+    }
+
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn target(mut self, target: impl Into<InvokeTarget>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Calls `callback` with each progress update the frontend sends before its answer.
+    /// It runs on the transport's delivery path, so hand heavy work off, e.g. to a
+    /// `tokio::sync::watch` channel. Updates that don't decode as `T` are logged and skipped.
+    pub fn on_progress<T: DeserializeOwned>(
+        mut self,
+        callback: impl Fn(T) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(move |value| match serde_json::from_value(value) {
+            Ok(update) => callback(update),
+            Err(err) => log::warn!("[frontbridge] failed to decode progress update: {err}"),
+        }));
+        self
+    }
+
+    fn effective_timeout(&self, config: &BridgeConfig) -> Option<Duration> {
+        self.timeout.unwrap_or(config.default_timeout)
+    }
+}
+
+/// Per-label outcome of `gather_frontend`.
+#[derive(Debug)]
+pub struct Gathered<R> {
+    pub results: HashMap<String, std::result::Result<R, FrontInvokeError>>,
+}
+
+impl<R> Gathered<R> {
+    pub fn successes(&self) -> impl Iterator<Item = (&str, &R)> {
+        self.results
+            .iter()
+            .filter_map(|(label, result)| Some((label.as_str(), result.as_ref().ok()?)))
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &FrontInvokeError)> {
+        self.results
+            .iter()
+            .filter_map(|(label, result)| Some((label.as_str(), result.as_ref().err()?)))
+    }
+
+    /// Whether every addressed webview answered successfully.
+    pub fn is_complete(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+}
+
+/// Why `race` produced no winner.
+#[derive(Debug)]
+pub enum RaceError {
+    /// The call couldn't be made at all, e.g. the payload didn't serialize.
+    Invoke(FrontInvokeError),
+    /// Every webview failed; each label's error. Empty when no labels were given.
+    AllFailed {
+        method: String,
+        failures: HashMap<String, FrontInvokeError>,
+    },
+}
+
+impl std::fmt::Display for RaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invoke(err) => write!(f, "{err}"),
+            Self::AllFailed { method, failures } if failures.is_empty() => {
+                write!(f, "frontend invoke {method} had no webviews to race")
+            }
+            Self::AllFailed { method, failures } => write!(
+                f,
+                "frontend invoke {method} failed on all {} webviews",
+                failures.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RaceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Invoke(err) => err.source(),
+            Self::AllFailed { .. } => None,
+        }
+    }
+}
+
+struct QueuedRequest {
+    request: FrontInvokeRequest,
+    destination: Destination,
+    flushed: oneshot::Sender<Result<()>>,
+    /// When a queued notification is dropped. Requests have a caller who dequeues them on
+    /// timeout; notifications have nobody, so they carry the ready timeout themselves.
+    expires: Option<Instant>,
+}
+
+impl QueuedRequest {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+#[derive(Default)]
+struct WindowReadiness {
+    ready: bool,
+    watched: bool,
+    queue: VecDeque<QueuedRequest>,
+}
+
+impl WindowReadiness {
+    /// Drops notifications that waited longer than the ready timeout.
+    fn prune_expired(&mut self, label: &str) {
+        let now = Instant::now();
+        let before = self.queue.len();
+        self.queue.retain(|queued| !queued.is_expired(now));
+        let expired = before - self.queue.len();
+        if expired > 0 {
+            log::warn!("[frontbridge] frontend {label} not ready, dropped {expired} notifications");
+        }
+    }
+}
+
+enum Dispatch {
+    Ready(FrontInvokeRequest),
+    Queued(oneshot::Receiver<Result<()>>),
+    Full,
+}
+
+pub(crate) struct FrontInvokeState {
+    pub(crate) config: BridgeConfig,
+    pub(crate) transport: Arc<dyn Transport>,
+    pending: PendingTable,
+    windows: Mutex<HashMap<String, WindowReadiness>>,
+    handlers: HandlerRegistry,
+    closed: AtomicBool,
+}
+
+impl FrontInvokeState {
+    fn new(config: BridgeConfig, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            pending: PendingTable::new().log_traffic(config.log_traffic),
+            config,
+            transport,
+            windows: Mutex::new(HashMap::new()),
+            handlers: HandlerRegistry::new(),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops the transport and drops every pending and queued request, which
+    /// makes their callers return immediately.
+    fn close(&self) {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.transport.stop();
+        self.windows
+            .lock()
+            .expect("frontbridge window map poisoned")
+            .clear();
+        self.pending.clear();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Runs the registered handler for a frontend call and sends the reply back to `origin`,
+    /// the webview the transport received the call from.
+    pub(crate) fn serve_call(self: &Arc<Self>, origin: String, call: FrontCall) {
+        if self.is_closed() {
+            return;
+        }
+        let state = Arc::clone(self);
+        tauri::async_runtime::spawn(async move {
+            let method = call.method.clone();
+            let reply = state.handlers.call(call).await;
+            if state.config.log_traffic {
+                log::debug!(
+                    "[frontbridge] served {method} id={} success={}",
+                    reply.id,
+                    reply.success
+                );
+            }
+            let destination = Destination::Label {
+                target: EventTarget::AnyLabel {
+                    label: origin.clone(),
+                },
+                label: origin,
+            };
+            if let Err(err) = state.transport.send_reply(&destination, &reply) {
+                log::warn!(
+                    "[frontbridge] failed to reply to {method} id={}: {err:#}",
+                    reply.id
+                );
+            }
+        });
+    }
+
+    pub(crate) fn mark_ready(&self, label: &str) {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("frontbridge window map poisoned");
+        let window = windows.entry(label.to_string()).or_default();
+        window.ready = true;
+        window.prune_expired(label);
+        if window.queue.is_empty() {
+            return;
+        }
+        // Send outside the lock: a transport may answer synchronously, running resolve and
+        // progress callbacks that call back into the bridge.
+        let queued: Vec<_> = window.queue.drain(..).collect();
+        drop(windows);
+        log::info!(
+            "[frontbridge] frontend {label} ready, flushing {} queued requests",
+            queued.len()
+        );
+        for queued in queued {
+            let result = self
+                .transport
+                .send_request(&queued.destination, &queued.request);
+            let _ = queued.flushed.send(result);
+        }
+    }
+
+    /// Fails requests bound to the webview `label` once `window`, which hosts it, is
+    /// destroyed, unless already watching it. Child webviews have no destroy event of their
+    /// own, so their window's is used.
+    fn watch<R: Runtime>(self: &Arc<Self>, label: &str, window: &Window<R>) {
+        let label = label.to_string();
+        {
+            let mut windows = self
+                .windows
+                .lock()
+                .expect("frontbridge window map poisoned");
+            let entry = windows.entry(label.clone()).or_default();
+            if entry.watched {
+                return;
+            }
+            entry.watched = true;
+        }
+        let state = Arc::clone(self);
+        window.on_window_event(move |event| {
+            if let WindowEvent::Destroyed = event {
+                state.frontend_gone(&label, FrontendGoneReason::Destroyed);
+            }
+        });
+    }
+
+    /// Watches the webview window `label`, if there is one.
+    fn watch_window<R: Runtime>(self: &Arc<Self>, app_handle: &AppHandle<R>, label: &str) {
+        if let Some(window) = app_handle.get_webview_window(label) {
+            self.watch(label, &window.as_ref().window());
+        }
+    }
+
+    /// Settles every request addressed to `label` with `FrontendGone`.
+    ///
+    /// On navigation only requests that already reached the old page are failed; queued ones
+    /// stay put and are flushed once the new page announces itself.
+    pub(crate) fn frontend_gone(&self, label: &str, reason: FrontendGoneReason) {
+        self.reset(label, reason, reason == FrontendGoneReason::Destroyed);
+    }
+
+    /// A webview was created under `label`. Whatever held the label before is gone, so its
+    /// readiness is cleared and requests it was handling fail; queued ones wait for the new page.
+    pub(crate) fn webview_created(&self, label: &str) {
+        self.reset(label, FrontendGoneReason::Destroyed, false);
+    }
+
+    fn reset(&self, label: &str, reason: FrontendGoneReason, drop_queue: bool) {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("frontbridge window map poisoned");
+        let queued = if drop_queue {
+            windows
+                .remove(label)
+                .map(|window| window.queue)
+                .unwrap_or_default()
+        } else {
+            let window = windows.entry(label.to_string()).or_default();
+            window.ready = false;
+            // A new webview needs its own watch.
+            window.watched &= reason == FrontendGoneReason::Navigated;
+            VecDeque::new()
+        };
+        // Queued requests never reached the page; they are settled through their flush channel.
+        let waiting: Vec<u64> = windows
+            .get(label)
+            .map_or(&queued, |window| &window.queue)
+            .iter()
+            .map(|q| q.request.id)
+            .collect();
+        let failed = self.pending.fail_label(label, reason, &waiting);
+        drop(windows);
+
+        if failed > 0 || !queued.is_empty() {
+            log::warn!(
+                "[frontbridge] frontend {label} {reason}, failing {} in-flight requests",
+                failed + queued.len()
+            );
+        }
+        for queued in queued {
+            let _ = queued.flushed.send(Err(FrontendGone {
+                method: queued.request.method,
+                label: label.to_string(),
+                reason,
+            }
+            .into()));
+        }
+        self.transport.frontend_gone(label);
+    }
+
+    fn is_ready(&self, label: &str) -> bool {
+        self.windows
+            .lock()
+            .expect("frontbridge window map poisoned")
+            .get(label)
+            .is_some_and(|window| window.ready)
+    }
+
+    fn dispatch(
+        &self,
+        label: &str,
+        destination: &Destination,
+        request: FrontInvokeRequest,
+    ) -> Dispatch {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("frontbridge window map poisoned");
+        let window = windows.entry(label.to_string()).or_default();
+        if window.ready || !self.config.wait_for_ready {
+            return Dispatch::Ready(request);
+        }
+        window.prune_expired(label);
+        if window.queue.len() >= self.config.ready_queue_capacity {
+            return Dispatch::Full;
+        }
+        let (flushed, rx) = oneshot::channel();
+        let expires = request
+            .notify
+            .then(|| Instant::now() + self.config.ready_timeout);
+        window.queue.push_back(QueuedRequest {
+            request,
+            destination: destination.clone(),
+            flushed,
+            expires,
+        });
+        Dispatch::Queued(rx)
+    }
+
+    fn dequeue_everywhere(&self, id: u64) {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("frontbridge window map poisoned");
+        for window in windows.values_mut() {
+            window.queue.retain(|queued| queued.request.id != id);
+        }
+    }
+
+    /// Returns `false` if the request already left the queue.
+    fn dequeue(&self, label: &str, id: u64) -> bool {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("frontbridge window map poisoned");
+        let Some(window) = windows.get_mut(label) else {
+            return false;
+        };
+        let before = window.queue.len();
+        window.queue.retain(|queued| queued.request.id != id);
+        window.queue.len() != before
+    }
+
+    /// Settles the request `resp` answers; see `PendingTable::resolve`. Rejections go to the
+    /// security hook.
+    pub(crate) fn resolve(
+        &self,
+        resp: FrontInvokeResponse,
+        origin: Option<&str>,
+    ) -> std::result::Result<(), RejectReason> {
+        self.pending.resolve(resp, origin).map_err(|rejected| {
+            let reason = rejected.reason;
+            report_rejected(self.config.security_hook.as_ref(), rejected);
+            reason
+        })
+    }
+
+    fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Deregisters a pending request on every exit path, including the caller dropping the future.
+/// A request that reached the frontend but was never answered is cancelled there as well.
+struct PendingGuard {
+    state: Arc<FrontInvokeState>,
+    destination: Destination,
+    id: u64,
+    sent: bool,
+    settled: bool,
+}
+
+impl PendingGuard {
+    fn new(state: Arc<FrontInvokeState>, destination: &Destination, id: u64) -> Self {
+        Self {
+            state,
+            destination: destination.clone(),
+            id,
+            sent: false,
+            settled: false,
+        }
+    }
+
+    /// Lets the frontend send `credit` more chunks of this stream.
+    fn grant_credit(&self, credit: u32) {
+        let credit = FrontInvokeCredit {
+            id: self.id,
+            credit,
+        };
+        if let Err(err) = self.state.transport.send_credit(&self.destination, &credit) {
+            log::warn!(
+                "[frontbridge] failed to grant credit to stream id={}: {err:#}",
+                self.id
+            );
+        }
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.state.pending.remove(self.id);
+        if !self.sent {
+            self.state.dequeue_everywhere(self.id);
+        }
+        if self.sent && !self.settled {
+            let cancel = FrontInvokeCancel { id: self.id };
+            if let Err(err) = self.state.transport.send_cancel(&self.destination, &cancel) {
+                log::warn!(
+                    "[frontbridge] failed to cancel request id={}: {err:#}",
+                    self.id
+                );
+            }
+        }
+    }
+}
+
+enum StreamStep {
+    Chunk(Value),
+    /// Data carried by the final response.
+    Last(Value),
+    End,
+    Failed(FrontInvokeError),
+}
+
+/// A streaming call that reached the frontend; its guard cancels it when dropped early.
+struct StreamCall {
+    method: String,
+    guard: PendingGuard,
+    chunks: mpsc::Receiver<Value>,
+    /// Chunks taken since credit was last granted; granted back once `credit_batch` add up.
+    consumed: u32,
+    credit_batch: u32,
+    end: oneshot::Receiver<Settlement>,
+    timeout: Option<Duration>,
+    cancel: Option<CancellationToken>,
+}
+
+impl StreamCall {
+    fn into_stream<T: DeserializeOwned>(
+        self,
+    ) -> impl Stream<Item = std::result::Result<T, FrontInvokeError>> {
+        stream::unfold(Some(self), |call| async move {
+            let mut call = call?;
+            let step = call.next().await;
+            let decode = |value| {
+                serde_json::from_value(value).map_err(|source| FrontInvokeError::Decode {
+                    method: call.method.clone(),
+                    source,
+                })
+            };
+            let (item, more) = match step {
+                StreamStep::Chunk(value) => (decode(value), true),
+                StreamStep::Last(value) => (decode(value), false),
+                StreamStep::End => return None,
+                StreamStep::Failed(err) => (Err(err), false),
+            };
+            Some((item, more.then_some(call)))
+        })
+    }
+
+    /// Waits for the next chunk, or for the final response once the frontend ended the stream.
+    async fn next(&mut self) -> StreamStep {
+        let Self {
+            method,
+            guard,
+            chunks,
+            consumed,
+            credit_batch,
+            end,
+            timeout,
+            cancel,
+        } = self;
+        let wait = async {
+            if let Some(value) = chunks.recv().await {
+                *consumed += 1;
+                if *consumed >= *credit_batch {
+                    guard.grant_credit(std::mem::take(consumed));
+                }
+                return StreamStep::Chunk(value);
+            }
+            // Every chunk is drained; the sender went away with the pending entry.
+            let settled = end.await;
+            let resp = match settled {
+                Ok(Ok(resp)) => resp,
+                Ok(Err(err)) => {
+                    // An overflowed stream is still running on the frontend; let the guard
+                    // cancel it.
+                    guard.settled = !matches!(
+                        &err,
+                        FrontInvokeError::Transport { source, .. } if source.is::<StreamOverflow>()
+                    );
+                    return StreamStep::Failed(err);
+                }
+                Err(_) => return StreamStep::Failed(dropped(method)),
+            };
+            guard.settled = true;
+            match resp.into_result::<Value>(method) {
+                Ok(Value::Null) => StreamStep::End,
+                Ok(value) => StreamStep::Last(value),
+                Err(err) => StreamStep::Failed(err),
+            }
+        };
+        let wait = async {
+            match *timeout {
+                Some(timeout) => tokio::time::timeout(timeout, wait)
+                    .await
+                    .unwrap_or_else(|_| {
+                        StreamStep::Failed(
+                            FrontInvokeTimeout {
+                                method: method.clone(),
+                                timeout,
+                            }
+                            .into(),
+                        )
+                    }),
+                None => wait.await,
+            }
+        };
+        match cancel {
+            Some(token) => tokio::select! {
+                step = wait => step,
+                _ = token.cancelled() => StreamStep::Failed(
+                    FrontInvokeCancelled { method: method.clone() }.into()
+                ),
+            },
+            None => wait.await,
+        }
+    }
+}
+
+/// Where a request ends up once its `InvokeTarget` has been looked up.
+#[derive(Debug, Clone)]
+pub enum Destination {
+    /// A single labelled webview, gated on its readiness.
+    Label {
+        label: String,
+        target: EventTarget,
+    },
+    Broadcast(EventTarget),
+}
+
+impl Destination {
+    fn window<R: Runtime>(window: WebviewWindow<R>) -> Self {
+        let label = window.label().to_string();
+        Self::Label {
+            target: EventTarget::webview_window(&label),
+            label,
+        }
+    }
+
+    fn resolve<R: Runtime>(app_handle: &AppHandle<R>, target: &InvokeTarget) -> Result<Self> {
+        match target {
+            InvokeTarget::Main => Ok(app_handle
+                .get_webview_window(MAIN_LABEL)
+                .map_or(Self::Broadcast(EventTarget::Any), Self::window)),
+            InvokeTarget::Window(label) => app_handle
+                .get_webview_window(label)
+                .map(Self::window)
+                .ok_or_else(|| anyhow!("frontend window {label} not found")),
+            InvokeTarget::Webview(label) => Ok(Self::Label {
+                label: label.clone(),
+                target: EventTarget::webview(label),
+            }),
+            InvokeTarget::Event(target) => Ok(match target {
+                EventTarget::AnyLabel { label }
+                | EventTarget::Window { label }
+                | EventTarget::Webview { label }
+                | EventTarget::WebviewWindow { label } => Self::Label {
+                    label: label.clone(),
+                    target: target.clone(),
+                },
+                _ => Self::Broadcast(target.clone()),
+            }),
+            InvokeTarget::Focused => app_handle
+                .webview_windows()
+                .into_values()
+                .find(|window| window.is_focused().unwrap_or(false))
+                .map(Self::window)
+                .ok_or_else(|| anyhow!("no focused frontend window")),
+            InvokeTarget::Any => Ok(Self::Broadcast(EventTarget::Any)),
+        }
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Label { label, .. } => Some(label),
+            Self::Broadcast(_) => None,
+        }
+    }
+
+    pub fn event_target(&self) -> &EventTarget {
+        match self {
+            Self::Label { target, .. } | Self::Broadcast(target) => target,
+        }
+    }
+}
+
+/// A bridge bound to one `AppHandle`, kept in Tauri managed state.
+///
+/// `FrontBridge::init` creates and manages it explicitly; the free `invoke_frontend*`
+/// functions do the same lazily on first use. Retrieve it with `app.state::<FrontBridge>()`
+/// (or `FrontBridge<MockRuntime>` under `tauri::test`).
+pub struct FrontBridge<R: Runtime = Wry> {
+    app_handle: AppHandle<R>,
+    pub(crate) state: Arc<FrontInvokeState>,
+}
+
+impl<R: Runtime> Clone for FrontBridge<R> {
+    fn clone(&self) -> Self {
+        Self {
+            app_handle: self.app_handle.clone(),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<R: Runtime> FrontBridge<R> {
+    /// Creates an unmanaged bridge with the default settings, talking over Tauri events.
+    /// Use `Builder::build_bridge` for other settings.
+    pub fn new(app_handle: &AppHandle<R>) -> Self {
+        Self::with_config(app_handle, BridgeConfig::default())
+    }
+
+    pub(crate) fn with_config(app_handle: &AppHandle<R>, config: BridgeConfig) -> Self {
+        let transport = config
+            .transport
+            .clone()
+            .unwrap_or_else(|| Arc::new(EventTransport::with_config(app_handle, &config)));
+        let state = Arc::new(FrontInvokeState::new(config, transport));
+        if let Err(err) = state.transport.start(Inbound::new(&state)) {
+            log::error!("[frontbridge] failed to start transport: {err:#}");
+        }
+        Self {
+            app_handle: app_handle.clone(),
+            state,
+        }
+    }
+
+    /// Returns the bridge managed by `app_handle`, creating and managing one if needed.
+    pub fn init(app_handle: &AppHandle<R>) -> Self {
+        if let Some(bridge) = app_handle.try_state::<Self>() {
+            return bridge.inner().clone();
+        }
+        let bridge = Self::new(app_handle);
+        if !app_handle.manage(bridge.clone()) {
+            // Lost a race with another caller; keep theirs.
+            bridge.shutdown();
+        }
+        app_handle.state::<Self>().inner().clone()
+    }
+
+    pub fn app_handle(&self) -> &AppHandle<R> {
+        &self.app_handle
+    }
+
+    /// Stops the bridge: the transport is stopped, in-flight calls fail and later calls are refused.
+    pub fn shutdown(&self) {
+        self.state.close();
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.state.pending_count()
+    }
+
+    /// The deadline applied to calls that don't set their own; `None` waits forever.
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.state.config.default_timeout
+    }
+
+    /// Whether the window labelled `label` has announced itself via `READY_EVENT`.
+    pub fn frontend_ready(&self, label: &str) -> bool {
+        self.state.is_ready(label)
+    }
+
+    /// Lets the frontend call `method` on the backend; see `HandlerRegistry::handle`.
+    ///
+    /// ```ignore
+    /// bridge.handle("settings.get", async |key: String| Ok(store.get(&key)));
+    /// ```
+    pub fn handle<P, T, F, Fut>(&self, method: impl Into<String>, handler: F)
+    where
+        P: DeserializeOwned,
+        T: Serialize,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.state.handlers.handle(method, handler);
+    }
+
+    /// Stops serving `method`; later calls to it fail. Returns `false` if it wasn't handled.
+    pub fn remove_handler(&self, method: &str) -> bool {
+        self.state.handlers.remove(method)
+    }
+
+    /// Fails requests sent to `webview` once the window hosting it is destroyed. Webview
+    /// windows are watched on their first request; the plugin also watches every webview it
+    /// sees created. Without the plugin, call this for child webviews.
+    pub fn watch_webview(&self, webview: &Webview<R>) {
+        self.state.watch(webview.label(), &webview.window());
+    }
+
+    /// Fails requests sent to `webview` when it starts loading a new page.
+    pub fn handle_page_load(&self, webview: &Webview<R>, payload: &PageLoadPayload<'_>) {
+        if let PageLoadEvent::Started = payload.event() {
+            self.state
+                .frontend_gone(webview.label(), FrontendGoneReason::Navigated);
+        }
+    }
+
+    /// Sends `method` as a notification: no id, no pending entry, no reply. Unlike `invoke`
+    /// this doesn't wait for the target to become ready; the notification is queued for it
+    /// and dropped if the target isn't ready within the ready timeout.
+    pub fn notify<P: Serialize>(&self, method: impl Into<String>, payload: P) -> Result<()> {
+        self.notify_with(method, payload, InvokeOptions::default())
+    }
+
+    /// `notify` with a target from `options`; their timeout and cancel token don't apply.
+    pub fn notify_with<P: Serialize>(
+        &self,
+        method: impl Into<String>,
+        payload: P,
+        options: InvokeOptions,
+    ) -> Result<()> {
+        let method = method.into();
+        if self.state.is_closed() {
+            return Err(anyhow!("frontbridge has been shut down"));
+        }
+        let state = &self.state;
+        let target = options
+            .target
+            .as_ref()
+            .unwrap_or(&state.config.default_target);
+        let destination = Destination::resolve(&self.app_handle, target)?;
+        let payload = serde_json::to_value(payload).context("serialize frontend payload")?;
+        let request = FrontInvokeRequest::notification(method.clone(), Some(payload));
+        if state.config.log_traffic {
+            log::debug!("[frontbridge] -> {method} (notify)");
+        }
+        let Destination::Label { label, .. } = &destination else {
+            return state.transport.send_request(&destination, &request);
+        };
+        state.watch_window(&self.app_handle, label);
+        match state.dispatch(label, &destination, request) {
+            Dispatch::Ready(request) => state.transport.send_request(&destination, &request),
+            Dispatch::Queued(_) => Ok(()),
+            Dispatch::Full => {
+                log::warn!("[frontbridge] request queue for {label} is full");
+                Err(FrontendNotReady {
+                    method,
+                    label: label.clone(),
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Calls the frontend method `M`, with its name and types taken from the `FrontMethod` impl.
+    pub async fn invoke<M: FrontMethod>(
+        &self,
+        params: M::Params,
+    ) -> std::result::Result<M::Output, FrontInvokeError> {
+        self.invoke_with::<M>(params, InvokeOptions::default())
+            .await
+    }
+
+    pub async fn invoke_with<M: FrontMethod>(
+        &self,
+        params: M::Params,
+        options: InvokeOptions,
+    ) -> std::result::Result<M::Output, FrontInvokeError> {
+        self.invoke_by_name_with(M::NAME, params, options).await
+    }
+
+    /// Calls `method` with the payload and answer types chosen at the call site. Prefer
+    /// `invoke` with a `FrontMethod`, which the compiler checks.
+    pub async fn invoke_by_name<T, P>(
+        &self,
+        method: impl Into<String>,
+        payload: P,
+    ) -> std::result::Result<T, FrontInvokeError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        self.invoke_by_name_with(method, payload, InvokeOptions::default())
+            .await
+    }
+
+    pub async fn invoke_by_name_with<T, P>(
+        &self,
+        method: impl Into<String>,
+        payload: P,
+        options: InvokeOptions,
+    ) -> std::result::Result<T, FrontInvokeError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+    {
+        let method = method.into();
+        let transport = |err| FrontInvokeError::transport(method.clone(), err);
+        let destination = self.destination(&method, &options)?;
+        let (state, app_handle) = (&self.state, &self.app_handle);
+        let payload_value = serde_json::to_value(payload)
+            .context("serialize frontend payload")
+            .map_err(transport)?;
+        let (request, rx) = state
+            .pending
+            .request(
+                method.clone(),
+                Some(payload_value),
+                destination.label().map(str::to_string),
+            )
+            .map_err(transport)?;
+        let id = request.id;
+        let mut guard = PendingGuard::new(Arc::clone(state), &destination, id);
+        if let Some(progress) = &options.progress {
+            state.pending.on_progress(id, Arc::clone(progress));
+        }
+
+        let wait = async {
+            dispatch_request(state, app_handle, &destination, request)
+                .await
+                .map_err(transport)?;
+            guard.sent = true;
+            if state.config.log_traffic {
+                log::debug!("[frontbridge] -> {method} id={id}");
+            }
+            Ok::<_, FrontInvokeError>(rx.await)
+        };
+        // One deadline covers both waiting for the target to be ready and for its answer.
+        let wait = async {
+            match options.effective_timeout(&state.config) {
+                Some(timeout) => tokio::time::timeout(timeout, wait)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(FrontInvokeTimeout {
+                            method: method.clone(),
+                            timeout,
+                        }
+                        .into())
+                    }),
+                None => wait.await,
+            }
+        };
+        let received = match &options.cancel {
+            Some(token) => tokio::select! {
+                received = wait => received?,
+                _ = token.cancelled() => {
+                    return Err(FrontInvokeCancelled { method: method.clone() }.into());
+                }
+            },
+            None => wait.await?,
+        };
+        guard.settled = true;
+        received
+            .map_err(|_| dropped(&method))??
+            .into_result(&method)
+    }
+
+    /// Like `invoke_by_name`, but a frontend error that decodes as `E` comes back as
+    /// `FrontError::App`; see `ErrorPayload::decode`.
+    pub async fn invoke_typed<T, E, P>(
+        &self,
+        method: impl Into<String>,
+        payload: P,
+    ) -> std::result::Result<T, FrontError<E>>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+        P: Serialize,
+    {
+        self.invoke_typed_with(method, payload, InvokeOptions::default())
+            .await
+    }
+
+    pub async fn invoke_typed_with<T, E, P>(
+        &self,
+        method: impl Into<String>,
+        payload: P,
+        options: InvokeOptions,
+    ) -> std::result::Result<T, FrontError<E>>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+        P: Serialize,
+    {
+        self.invoke_by_name_with(method, payload, options)
+            .await
+            .map_err(FrontError::from)
+    }
+
+    /// Calls `method` as a stream: the frontend may answer with any number of chunk responses
+    /// before its final one. Each chunk is yielded in order; a final response with data yields
+    /// that as the last item, a failed one as the last error.
+    ///
+    /// The options' timeout bounds sending the call, including any wait for the target to be
+    /// ready, and then the wait for each item rather than the whole stream. The frontend may
+    /// send `STREAM_CAPACITY` chunks ahead of the consumer and is granted more credit as the
+    /// stream is polled, so a slow consumer slows the frontend down rather than losing chunks.
+    /// A frontend that ignores its credit ends the stream, after the buffered items, with a
+    /// `StreamOverflow` error. Dropping the stream cancels the call.
+    pub fn invoke_stream<T, P, M>(
+        &self,
+        method: M,
+        payload: P,
+    ) -> impl Stream<Item = std::result::Result<T, FrontInvokeError>> + Send + use<R, T, P, M>
+    where
+        T: DeserializeOwned + Send + 'static,
+        P: Serialize,
+        M: Into<String>,
+    {
+        self.invoke_stream_with(method, payload, InvokeOptions::default())
+    }
+
+    pub fn invoke_stream_with<T, P, M>(
+        &self,
+        method: M,
+        payload: P,
+        options: InvokeOptions,
+    ) -> impl Stream<Item = std::result::Result<T, FrontInvokeError>> + Send + use<R, T, P, M>
+    where
+        T: DeserializeOwned + Send + 'static,
+        P: Serialize,
+        M: Into<String>,
+    {
+        let bridge = self.clone();
+        let method = method.into();
+        let payload = serde_json::to_value(payload)
+            .context("serialize frontend payload")
+            .map_err(|err| FrontInvokeError::transport(method.clone(), err));
+        let open = async move { bridge.open_stream(method, payload?, options).await };
+        stream::once(open).flat_map(|opened| match opened {
+            Ok(call) => call.into_stream().left_stream(),
+            Err(err) => stream::iter([Err(err)]).right_stream(),
+        })
+    }
+
+    async fn open_stream(
+        &self,
+        method: String,
+        payload: Value,
+        options: InvokeOptions,
+    ) -> std::result::Result<StreamCall, FrontInvokeError> {
+        let transport = |err| FrontInvokeError::transport(method.clone(), err);
+        let destination = self.destination(&method, &options)?;
+        let state = &self.state;
+        let (request, chunks, end) = state
+            .pending
+            .request_stream(
+                method.clone(),
+                Some(payload),
+                destination.label().map(str::to_string),
+                state.config.stream_capacity,
+            )
+            .map_err(transport)?;
+        let id = request.id;
+        // Credit goes back in batches of half the buffer, so the frontend rarely has to wait.
+        let credit_batch = request.credit.map_or(1, |credit| (credit / 2).max(1));
+        let mut guard = PendingGuard::new(Arc::clone(state), &destination, id);
+        if let Some(progress) = &options.progress {
+            state.pending.on_progress(id, Arc::clone(progress));
+        }
+        let timeout = options.effective_timeout(&state.config);
+        let dispatch = async {
+            let sent = dispatch_request(state, &self.app_handle, &destination, request);
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, sent)
+                    .await
+                    .map_err(|_| {
+                        FrontInvokeError::from(FrontInvokeTimeout {
+                            method: method.clone(),
+                            timeout,
+                        })
+                    })?
+                    .map_err(transport),
+                None => sent.await.map_err(transport),
+            }
+        };
+        match &options.cancel {
+            Some(token) => tokio::select! {
+                sent = dispatch => sent?,
+                _ = token.cancelled() => {
+                    return Err(FrontInvokeCancelled { method: method.clone() }.into());
+                }
+            },
+            None => dispatch.await?,
+        }
+        guard.sent = true;
+        if state.config.log_traffic {
+            log::debug!("[frontbridge] -> {method} id={id} (stream)");
+        }
+        Ok(StreamCall {
+            timeout,
+            cancel: options.cancel,
+            method,
+            guard,
+            chunks,
+            consumed: 0,
+            credit_batch,
+            end,
+        })
+    }
+
+    /// Refuses calls that are already cancelled or hit a closed bridge, then looks up where
+    /// the call goes.
+    fn destination(
+        &self,
+        method: &str,
+        options: &InvokeOptions,
+    ) -> std::result::Result<Destination, FrontInvokeError> {
+        if options
+            .cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(FrontInvokeCancelled {
+                method: method.to_string(),
+            }
+            .into());
+        }
+        if self.state.is_closed() {
+            return Err(FrontInvokeError::transport(
+                method,
+                anyhow!("frontbridge has been shut down"),
+            ));
+        }
+        let target = options
+            .target
+            .as_ref()
+            .unwrap_or(&self.state.config.default_target);
+        Destination::resolve(&self.app_handle, target)
+            .map_err(|err| FrontInvokeError::transport(method, err))
+    }
+
+    /// Sends one request to each labelled webview window and waits for all of them.
+    ///
+    /// The options' timeout is the collection deadline shared by every label; whatever hasn't
+    /// answered by then is reported as `FrontInvokeError::Timeout` in its slot. Pass
+    /// `app_handle.webview_windows().into_keys()` to address every open window.
+    pub async fn gather<T, P, L>(
+        &self,
+        labels: impl IntoIterator<Item = L>,
+        method: impl Into<String>,
+        payload: P,
+        options: InvokeOptions,
+    ) -> std::result::Result<Gathered<T>, FrontInvokeError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+        L: Into<String>,
+    {
+        let mut calls = fan_out(self, labels, method.into(), payload, options)?;
+        let mut results = HashMap::new();
+        while let Some((label, result)) = calls.next().await {
+            results.insert(label, result);
+        }
+        Ok(Gathered { results })
+    }
+
+    /// Sends one request to each labelled webview window and returns the first successful answer
+    /// with the label that produced it. The remaining requests are cancelled.
+    pub async fn race<T, P, L>(
+        &self,
+        labels: impl IntoIterator<Item = L>,
+        method: impl Into<String>,
+        payload: P,
+        options: InvokeOptions,
+    ) -> std::result::Result<(String, T), RaceError>
+    where
+        T: DeserializeOwned,
+        P: Serialize,
+        L: Into<String>,
+    {
+        let method = method.into();
+        let mut calls =
+            fan_out(self, labels, method.clone(), payload, options).map_err(RaceError::Invoke)?;
+        let mut failures = HashMap::new();
+        while let Some((label, result)) = calls.next().await {
+            match result {
+                Ok(value) => return Ok((label, value)),
+                Err(err) => {
+                    failures.insert(label, err);
+                }
+            }
+        }
+        Err(RaceError::AllFailed { method, failures })
+    }
+}
+
+pub fn pending_count(app_handle: &AppHandle<impl Runtime>) -> usize {
+    FrontBridge::init(app_handle).pending_count()
+}
+
+/// Whether the window labelled `label` has announced itself via `READY_EVENT`.
+pub fn frontend_ready(app_handle: &AppHandle<impl Runtime>, label: &str) -> bool {
+    FrontBridge::init(app_handle).frontend_ready(label)
+}
+
+/// Fails requests sent to `webview` when it starts loading a new page.
+///
+/// The plugin wires this up; without it, call it from `tauri::Builder::on_page_load`.
+/// Window destruction is tracked automatically either way.
+pub fn handle_page_load<R: Runtime>(webview: &Webview<R>, payload: &PageLoadPayload<'_>) {
+    if let Some(bridge) = webview.try_state::<FrontBridge<R>>() {
+        bridge.handle_page_load(webview, payload);
+    }
+}
+
+fn dropped(method: &str) -> FrontInvokeError {
+    FrontInvokeError::transport(
+        method,
+        anyhow!("frontend invoke {method} dropped without response"),
+    )
+}
+
+/// Emits the request once the target webview is ready, queueing it until then.
+/// Broadcasts are emitted immediately.
+async fn dispatch_request<R: Runtime>(
+    state: &Arc<FrontInvokeState>,
+    app_handle: &AppHandle<R>,
+    destination: &Destination,
+    request: FrontInvokeRequest,
+) -> Result<()> {
+    let Destination::Label { label, .. } = destination else {
+        return state.transport.send_request(destination, &request);
+    };
+    let (id, method) = (request.id, request.method.clone());
+    let not_ready = || FrontendNotReady {
+        method: method.clone(),
+        label: label.clone(),
+    };
+    state.watch_window(app_handle, label);
+    let mut flushed = match state.dispatch(label, destination, request) {
+        Dispatch::Ready(request) => return state.transport.send_request(destination, &request),
+        Dispatch::Queued(flushed) => flushed,
+        Dispatch::Full => {
+            log::warn!("[frontbridge] request queue for {label} is full");
+            return Err(not_ready().into());
+        }
+    };
+    match tokio::time::timeout(state.config.ready_timeout, &mut flushed).await {
+        Ok(result) => result.unwrap_or_else(|_| Err(anyhow!("queued request {id} dropped"))),
+        Err(_) if state.dequeue(label, id) => Err(not_ready().into()),
+        // Flushed concurrently with the timeout; the outcome is already on its way.
+        Err(_) => flushed
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("queued request {id} dropped"))),
+    }
+}
+
+pub async fn invoke_frontend<R, P>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+) -> std::result::Result<R, FrontInvokeError>
+where
+    R: DeserializeOwned,
+    P: Serialize,
+{
+    invoke_frontend_with(app_handle, method, payload, InvokeOptions::default()).await
+}
+
+pub async fn invoke_frontend_with<R, P>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+    options: InvokeOptions,
+) -> std::result::Result<R, FrontInvokeError>
+where
+    R: DeserializeOwned,
+    P: Serialize,
+{
+    FrontBridge::init(app_handle)
+        .invoke_by_name_with(method, payload, options)
+        .await
+}
+
+/// Free-function form of `FrontBridge::invoke` on the app's managed bridge.
+pub async fn invoke_frontend_method<M: FrontMethod>(
+    app_handle: &AppHandle<impl Runtime>,
+    params: M::Params,
+) -> std::result::Result<M::Output, FrontInvokeError> {
+    invoke_frontend_method_with::<M>(app_handle, params, InvokeOptions::default()).await
+}
+
+pub async fn invoke_frontend_method_with<M: FrontMethod>(
+    app_handle: &AppHandle<impl Runtime>,
+    params: M::Params,
+    options: InvokeOptions,
+) -> std::result::Result<M::Output, FrontInvokeError> {
+    FrontBridge::init(app_handle)
+        .invoke_with::<M>(params, options)
+        .await
+}
+
+/// Free-function form of `FrontBridge::invoke_typed` on the app's managed bridge.
+pub async fn invoke_frontend_typed<R, E, P>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+) -> std::result::Result<R, FrontError<E>>
+where
+    R: DeserializeOwned,
+    E: DeserializeOwned,
+    P: Serialize,
+{
+    invoke_frontend_typed_with(app_handle, method, payload, InvokeOptions::default()).await
+}
+
+pub async fn invoke_frontend_typed_with<R, E, P>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+    options: InvokeOptions,
+) -> std::result::Result<R, FrontError<E>>
+where
+    R: DeserializeOwned,
+    E: DeserializeOwned,
+    P: Serialize,
+{
+    FrontBridge::init(app_handle)
+        .invoke_typed_with(method, payload, options)
+        .await
+}
+
+/// Free-function form of `FrontBridge::invoke_stream` on the app's managed bridge.
+pub fn invoke_frontend_stream<Rt, R, P, M>(
+    app_handle: &AppHandle<Rt>,
+    method: M,
+    payload: P,
+) -> impl Stream<Item = std::result::Result<R, FrontInvokeError>> + Send + use<Rt, R, P, M>
+where
+    Rt: Runtime,
+    R: DeserializeOwned + Send + 'static,
+    P: Serialize,
+    M: Into<String>,
+{
+    FrontBridge::init(app_handle).invoke_stream(method, payload)
+}
+
+pub fn invoke_frontend_stream_with<Rt, R, P, M>(
+    app_handle: &AppHandle<Rt>,
+    method: M,
+    payload: P,
+    options: InvokeOptions,
+) -> impl Stream<Item = std::result::Result<R, FrontInvokeError>> + Send + use<Rt, R, P, M>
+where
+    Rt: Runtime,
+    R: DeserializeOwned + Send + 'static,
+    P: Serialize,
+    M: Into<String>,
+{
+    FrontBridge::init(app_handle).invoke_stream_with(method, payload, options)
+}
+
+/// Free-function form of `FrontBridge::notify` on the app's managed bridge.
+pub fn notify_frontend<P: Serialize>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+) -> Result<()> {
+    FrontBridge::init(app_handle).notify(method, payload)
+}
+
+pub fn notify_frontend_with<P: Serialize>(
+    app_handle: &AppHandle<impl Runtime>,
+    method: impl Into<String>,
+    payload: P,
+    options: InvokeOptions,
+) -> Result<()> {
+    FrontBridge::init(app_handle).notify_with(method, payload, options)
+}
+
+/// One webview's outcome in a fan-out call.
+type LabelResult<R> = (String, std::result::Result<R, FrontInvokeError>);
+
+fn fan_out<'a, Rt, R, P, L>(
+    bridge: &'a FrontBridge<Rt>,
+    labels: impl IntoIterator<Item = L>,
+    method: String,
+    payload: P,
+    options: InvokeOptions,
+) -> std::result::Result<
+    FuturesUnordered<impl Future<Output = LabelResult<R>> + 'a>,
+    FrontInvokeError,
+>
+where
+    R: DeserializeOwned,
+    P: Serialize,
+    L: Into<String>,
+    Rt: Runtime,
+{
+    let payload = serde_json::to_value(payload)
+        .context("serialize frontend payload")
+        .map_err(|err| FrontInvokeError::transport(method.clone(), err))?;
+    let timeout = options.effective_timeout(&bridge.state.config);
+    let started = tokio::time::Instant::now();
+    Ok(labels
+        .into_iter()
+        .map(|label| {
+            let label = label.into();
+            let options = options
+                .clone()
+                .no_timeout()
+                .target(InvokeTarget::window(&label));
+            let (method, payload) = (method.clone(), payload.clone());
+            async move {
+                let call = bridge.invoke_by_name_with(method.clone(), payload, options);
+                let result = match timeout {
+                    Some(timeout) => tokio::time::timeout_at(started + timeout, call)
+                        .await
+                        .unwrap_or_else(|_| Err(FrontInvokeTimeout { method, timeout }.into())),
+                    None => call.await,
+                };
+                (label, result)
+            }
+        })
+        .collect())
+}
+
+/// Free-function form of `FrontBridge::gather` on the app's managed bridge.
+pub async fn gather_frontend<R, P, L>(
+    app_handle: &AppHandle<impl Runtime>,
+    labels: impl IntoIterator<Item = L>,
+    method: impl Into<String>,
+    payload: P,
+    options: InvokeOptions,
+) -> std::result::Result<Gathered<R>, FrontInvokeError>
+where
+    R: DeserializeOwned,
+    P: Serialize,
+    L: Into<String>,
+{
+    FrontBridge::init(app_handle)
+        .gather(labels, method, payload, options)
+        .await
+}
+
+/// Free-function form of `FrontBridge::race` on the app's managed bridge.
+pub async fn race_frontend<R, P, L>(
+    app_handle: &AppHandle<impl Runtime>,
+    labels: impl IntoIterator<Item = L>,
+    method: impl Into<String>,
+    payload: P,
+    options: InvokeOptions,
+) -> std::result::Result<(String, R), RaceError>
+where
+    R: DeserializeOwned,
+    P: Serialize,
+    L: Into<String>,
+{
+    FrontBridge::init(app_handle)
+        .race(labels, method, payload, options)
+        .await
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::{Duration, Instant},
+    };
+
+    use futures_util::FutureExt;
+    use tauri::{
+        App, WebviewUrl, WebviewWindowBuilder,
+        test::{MockRuntime, mock_app},
+    };
+
+    use crate::{
+        Builder, CancellationToken, FrontBridge, FrontInvokeError, InvokeOptions, InvokeTarget,
+        RaceError, testing::FakeFrontend,
+    };
+
+    /// An app with a webview window for each of `labels`, talking to `fake`.
+    fn windows(
+        fake: &FakeFrontend,
+        labels: &[&str],
+    ) -> (App<MockRuntime>, FrontBridge<MockRuntime>) {
+        let app = mock_app();
+        for label in labels {
+            WebviewWindowBuilder::new(&app, *label, WebviewUrl::default())
+                .build()
+                .unwrap();
+        }
+        let bridge = fake.install(app.handle());
+        (app, bridge)
+    }
+
+    /// Labels of the calls the bridge cancelled, sorted.
+    fn cancelled_labels(fake: &FakeFrontend) -> Vec<String> {
+        let mut labels: Vec<_> = fake
+            .cancelled()
+            .into_iter()
+            .filter_map(|call| call.label)
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// A bridge that holds requests until their target announces itself.
+    fn gated(fake: &FakeFrontend) -> (App<MockRuntime>, FrontBridge<MockRuntime>) {
+        let app = mock_app();
+        let bridge = Builder::new()
+            .wait_for_ready(true)
+            .transport(fake.clone())
+            .build_bridge(app.handle());
+        (app, bridge)
+    }
+
+    #[test]
+    fn pending_requests_are_released_on_every_exit() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("hang").never_answer();
+
+        tauri::async_runtime::block_on(async {
+            // Polled inside the runtime, which the call's timer needs.
+            let mut dropped = Box::pin(bridge.invoke_by_name::<(), _>("hang", ()));
+            assert!((&mut dropped).now_or_never().is_none());
+            assert_eq!(bridge.pending_count(), 1);
+            drop(dropped);
+            assert_eq!(bridge.pending_count(), 0);
+
+            let options = InvokeOptions::new().timeout(Duration::from_millis(10));
+            let err = bridge
+                .invoke_by_name_with::<(), _>("hang", (), options)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Timeout(_)), "{err:?}");
+            assert_eq!(bridge.pending_count(), 0);
+
+            // JSON object keys must be strings.
+            let unserializable = HashMap::from([((1, 2), "pair")]);
+            let err = bridge
+                .invoke_by_name::<(), _>("hang", unserializable)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Transport { .. }), "{err:?}");
+            assert_eq!(bridge.pending_count(), 0);
+        });
+        fake.assert_call_order(&["hang", "hang"]);
+    }
+
+    #[test]
+    fn call_timeout_covers_the_ready_wait() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = gated(&fake);
+        fake.on("ping").respond("pong");
+
+        let options = InvokeOptions::new()
+            .target(InvokeTarget::webview("child"))
+            .timeout(Duration::from_millis(20));
+        let started = Instant::now();
+        let err = tauri::async_runtime::block_on(bridge.invoke_by_name_with::<String, _>(
+            "ping",
+            (),
+            options,
+        ))
+        .unwrap_err();
+        assert!(matches!(err, FrontInvokeError::Timeout(_)), "{err:?}");
+        assert!(
+            started.elapsed() < Duration::from_secs(1),
+            "{:?}",
+            started.elapsed()
+        );
+        assert_eq!(bridge.pending_count(), 0);
+
+        // The timed-out request left the queue, so readiness doesn't deliver it late.
+        fake.ready("child");
+        fake.assert_not_called("ping");
+    }
+
+    #[test]
+    fn flushing_the_queue_allows_reentrant_calls() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = gated(&fake);
+        fake.on("export").progress(50).respond("done");
+
+        let options = InvokeOptions::new()
+            .target(InvokeTarget::webview("child"))
+            .on_progress({
+                let bridge = bridge.clone();
+                move |_: u32| {
+                    // Runs while the queue is flushed; the window map must not be locked.
+                    assert!(bridge.frontend_ready("child"));
+                    bridge.notify("exportProgress", ()).unwrap();
+                }
+            });
+        let call = tauri::async_runtime::spawn({
+            let bridge = bridge.clone();
+            async move {
+                bridge
+                    .invoke_by_name_with::<String, _>("export", (), options)
+                    .await
+            }
+        });
+        tauri::async_runtime::block_on(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            fake.ready("child");
+            assert_eq!(call.await.unwrap().unwrap(), "done");
+        });
+        fake.assert_call_order(&["export", "exportProgress"]);
+    }
+
+    /// The ids of the calls `fake` received and the ids it was asked to cancel.
+    fn call_and_cancel_ids(fake: &FakeFrontend) -> (Vec<u64>, Vec<u64>) {
+        let ids = |calls: Vec<crate::testing::RecordedCall>| calls.iter().map(|c| c.id).collect();
+        (ids(fake.calls()), ids(fake.cancelled()))
+    }
+
+    #[test]
+    fn dropping_a_call_cancels_it() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("hang").never_answer();
+
+        tauri::async_runtime::block_on(async {
+            let mut call = Box::pin(bridge.invoke_by_name::<(), _>("hang", ()));
+            assert!((&mut call).now_or_never().is_none());
+            assert!(fake.cancelled().is_empty());
+        });
+
+        let (calls, cancels) = call_and_cancel_ids(&fake);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(cancels, calls);
+    }
+
+    #[test]
+    fn a_timed_out_call_is_cancelled() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("hang").never_answer();
+        fake.on("ping").respond("pong");
+
+        tauri::async_runtime::block_on(async {
+            let options = InvokeOptions::new().timeout(Duration::from_millis(10));
+            let err = bridge
+                .invoke_by_name_with::<(), _>("hang", (), options)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Timeout(_)), "{err:?}");
+            let pong: String = bridge.invoke_by_name("ping", ()).await.unwrap();
+            assert_eq!(pong, "pong");
+        });
+
+        let (calls, cancels) = call_and_cancel_ids(&fake);
+        assert_eq!(cancels, [calls[0]]);
+        fake.assert_not_cancelled("ping");
+    }
+
+    #[test]
+    fn a_cancelled_token_cancels_the_call() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("hang").never_answer();
+
+        let token = CancellationToken::new();
+        let options = InvokeOptions::new().cancel_token(token.clone());
+        let call = tauri::async_runtime::spawn({
+            let bridge = bridge.clone();
+            async move {
+                bridge
+                    .invoke_by_name_with::<(), _>("hang", (), options)
+                    .await
+            }
+        });
+        tauri::async_runtime::block_on(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            token.cancel();
+            let err = call.await.unwrap().unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Cancelled(_)), "{err:?}");
+        });
+
+        let (calls, cancels) = call_and_cancel_ids(&fake);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(cancels, calls);
+    }
+
+    #[test]
+    fn a_call_that_never_left_the_queue_is_not_cancelled() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = gated(&fake);
+        fake.on("ping").respond("pong");
+
+        let options = InvokeOptions::new().target(InvokeTarget::webview("child"));
+        tauri::async_runtime::block_on(async {
+            let mut call = Box::pin(bridge.invoke_by_name_with::<String, _>("ping", (), options));
+            assert!((&mut call).now_or_never().is_none());
+        });
+        fake.ready("child");
+
+        assert!(fake.calls().is_empty());
+        assert!(fake.cancelled().is_empty());
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn notifications_are_not_tracked_or_answered() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let rejected = Arc::new(AtomicUsize::new(0));
+        let bridge = Builder::new()
+            .transport(fake.clone())
+            .security_hook({
+                let rejected = Arc::clone(&rejected);
+                move |_| {
+                    rejected.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build_bridge(app.handle());
+        // Even a scripted method isn't answered when it arrives as a notification.
+        fake.on("toast").respond("ignored");
+
+        bridge.notify("toast", "Saved").unwrap();
+
+        assert_eq!(bridge.pending_count(), 0);
+        let calls = fake.calls();
+        assert_eq!((calls[0].method.as_str(), calls[0].id), ("toast", 0));
+        assert_eq!(rejected.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn queued_notifications_are_flushed_on_ready() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = gated(&fake);
+
+        let options = InvokeOptions::new().target(InvokeTarget::webview("child"));
+        bridge.notify_with("toast", "Saved", options).unwrap();
+        fake.assert_not_called("toast");
+
+        fake.ready("child");
+        fake.assert_called_with("toast", "Saved");
+    }
+
+    #[test]
+    fn expired_notifications_free_their_queue_slot() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = Builder::new()
+            .wait_for_ready(true)
+            .ready_queue_capacity(1)
+            .ready_timeout(Duration::from_millis(20))
+            .transport(fake.clone())
+            .build_bridge(app.handle());
+        fake.on("ping").respond("pong");
+        let to_child = || InvokeOptions::new().target(InvokeTarget::webview("child"));
+
+        bridge.notify_with("toast", "Saved", to_child()).unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+
+        tauri::async_runtime::block_on(async {
+            let mut call =
+                Box::pin(bridge.invoke_by_name_with::<String, _>("ping", (), to_child()));
+            // Queued rather than refused as `FrontendNotReady` by a full queue.
+            assert!((&mut call).now_or_never().is_none());
+            fake.ready("child");
+            assert_eq!(call.await.unwrap(), "pong");
+        });
+        fake.assert_call_order(&["ping"]);
+    }
+
+    #[test]
+    fn gather_reports_each_label_by_a_shared_deadline() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = windows(&fake, &["a", "b", "c", "d"]);
+        fake.on("scan").webview("a").respond(1);
+        fake.on("scan")
+            .webview("b")
+            .delay(Duration::from_millis(10))
+            .fail("busy");
+        fake.on("scan").never_answer();
+
+        let started = Instant::now();
+        let options = InvokeOptions::new().timeout(Duration::from_millis(50));
+        let gathered = tauri::async_runtime::block_on(bridge.gather::<u32, _, _>(
+            ["a", "b", "c", "d"],
+            "scan",
+            (),
+            options,
+        ))
+        .unwrap();
+        let elapsed = started.elapsed();
+
+        // The two silent labels share one deadline rather than waiting in turn.
+        assert!(elapsed >= Duration::from_millis(50), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(100), "{elapsed:?}");
+        let results = &gathered.results;
+        assert_eq!(results.len(), 4);
+        assert_eq!(*results["a"].as_ref().unwrap(), 1);
+        let busy = results["b"].as_ref().unwrap_err();
+        assert_eq!(busy.payload().map(|p| p.message.as_str()), Some("busy"));
+        for label in ["c", "d"] {
+            let err = results[label].as_ref().unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Timeout(_)), "{err:?}");
+        }
+        assert!(!gathered.is_complete());
+        assert_eq!(cancelled_labels(&fake), ["c", "d"]);
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn race_returns_the_first_success_and_cancels_the_rest() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = windows(&fake, &["a", "b", "c"]);
+        fake.on("pick").webview("a").fail("busy");
+        fake.on("pick")
+            .webview("b")
+            .delay(Duration::from_millis(10))
+            .respond("b's file");
+        fake.on("pick")
+            .webview("c")
+            .delay(Duration::from_secs(5))
+            .respond("c's file");
+
+        let winner = tauri::async_runtime::block_on(bridge.race::<String, _, _>(
+            ["a", "b", "c"],
+            "pick",
+            (),
+            InvokeOptions::new(),
+        ))
+        .unwrap();
+
+        assert_eq!(winner, ("b".to_string(), "b's file".to_string()));
+        // A failure doesn't win, and only the call still running is cancelled.
+        assert_eq!(cancelled_labels(&fake), ["c"]);
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn race_reports_every_failure_when_nothing_succeeds() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = windows(&fake, &["a", "b"]);
+        fake.on("pick").webview("a").fail("busy");
+        fake.on("pick").webview("b").never_answer();
+
+        let options = InvokeOptions::new().timeout(Duration::from_millis(20));
+        let err = tauri::async_runtime::block_on(bridge.race::<String, _, _>(
+            ["a", "b"],
+            "pick",
+            (),
+            options,
+        ))
+        .unwrap_err();
+
+        let RaceError::AllFailed { method, failures } = err else {
+            panic!("expected AllFailed, got {err:?}");
+        };
+        assert_eq!(method, "pick");
+        assert_eq!(failures.len(), 2);
+        assert_eq!(
+            failures["a"].payload().map(|p| p.message.as_str()),
+            Some("busy")
+        );
+        assert!(
+            matches!(failures["b"], FrontInvokeError::Timeout(_)),
+            "{:?}",
+            failures["b"]
+        );
+    }
+
+    #[test]
+    fn race_without_labels_fails_with_no_failures() {
+        let fake = FakeFrontend::new();
+        let (_app, bridge) = windows(&fake, &[]);
+
+        let err = tauri::async_runtime::block_on(bridge.race::<String, _, String>(
+            [],
+            "pick",
+            (),
+            InvokeOptions::new(),
+        ))
+        .unwrap_err();
+
+        assert!(
+            matches!(&err, RaceError::AllFailed { failures, .. } if failures.is_empty()),
+            "{err:?}"
+        );
+        assert_eq!(
+            err.to_string(),
+            "frontend invoke pick had no webviews to race"
+        );
+        assert!(fake.calls().is_empty());
+    }
+}
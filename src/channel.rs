@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+use crate::{
+    Destination, FrontInvokeCancel, FrontInvokeCredit, FrontInvokeRequest, FrontInvokeResponse,
+    Inbound, Transport,
+};
+
+/// What the bridge pushes down a connected channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ChannelMessage {
+    Request(FrontInvokeRequest),
+    Cancel(FrontInvokeCancel),
+    Credit(FrontInvokeCredit),
+    /// Answer to a call the webview made through the `call` command.
+    Reply(FrontInvokeResponse),
+}
+
+#[derive(Default)]
+struct ChannelInner {
+    channels: Mutex<HashMap<String, Channel<ChannelMessage>>>,
+    inbound: Mutex<Option<Inbound>>,
+}
+
+/// Pushes requests over a `tauri::ipc::Channel` each webview opens with the plugin's `connect`
+/// command, skipping the global event system. Replies come back through the `respond` command,
+/// and calls into backend handlers go through the `call` command.
+///
+/// Install it with `Builder::channel_transport`; connecting doubles as the ready announcement.
+#[derive(Clone, Default)]
+pub struct ChannelTransport {
+    inner: Arc<ChannelInner>,
+}
+
+impl ChannelTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `label`'s channel. The bridge drops it again when the webview goes away.
+    pub(crate) fn connect(&self, label: &str, channel: Channel<ChannelMessage>) {
+        self.inner
+            .channels
+            .lock()
+            .expect("frontbridge channel map poisoned")
+            .insert(label.to_string(), channel);
+        let inbound = self
+            .inner
+            .inbound
+            .lock()
+            .expect("frontbridge channel inbound poisoned")
+            .clone();
+        match inbound {
+            Some(inbound) => inbound.ready(label),
+            None => log::warn!("[frontbridge] channel from {label} connected before the bridge"),
+        }
+    }
+
+    fn send(&self, destination: &Destination, message: ChannelMessage) -> Result<()> {
+        let channels = self
+            .inner
+            .channels
+            .lock()
+            .expect("frontbridge channel map poisoned");
+        let Some(label) = destination.label() else {
+            // A broadcast isn't queued for channels that connect later; reaching none fails it.
+            let mut delivered = 0;
+            for (label, channel) in channels.iter() {
+                match channel.send(message.clone()) {
+                    Ok(()) => delivered += 1,
+                    Err(err) => {
+                        log::warn!("[frontbridge] failed to push to channel {label}: {err}")
+                    }
+                }
+            }
+            if delivered == 0 {
+                return Err(anyhow!("no channel connected"));
+            }
+            return Ok(());
+        };
+        let channel = channels
+            .get(label)
+            .ok_or_else(|| anyhow!("no channel connected for {label}"))?;
+        channel
+            .send(message)
+            .map_err(|err| anyhow!("push to channel {label}: {err}"))
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn start(&self, inbound: Inbound) -> Result<()> {
+        *self
+            .inner
+            .inbound
+            .lock()
+            .expect("frontbridge channel inbound poisoned") = Some(inbound);
+        Ok(())
+    }
+
+    fn send_request(&self, destination: &Destination, request: &FrontInvokeRequest) -> Result<()> {
+        self.send(destination, ChannelMessage::Request(request.clone()))
+    }
+
+    fn send_cancel(&self, destination: &Destination, cancel: &FrontInvokeCancel) -> Result<()> {
+        self.send(destination, ChannelMessage::Cancel(cancel.clone()))
+    }
+
+    fn send_credit(&self, destination: &Destination, credit: &FrontInvokeCredit) -> Result<()> {
+        self.send(destination, ChannelMessage::Credit(credit.clone()))
+    }
+
+    fn send_reply(&self, destination: &Destination, reply: &FrontInvokeResponse) -> Result<()> {
+        self.send(destination, ChannelMessage::Reply(reply.clone()))
+    }
+
+    /// Drops `label`'s channel, which died with its page.
+    fn frontend_gone(&self, label: &str) {
+        self.inner
+            .channels
+            .lock()
+            .expect("frontbridge channel map poisoned")
+            .remove(label);
+    }
+
+    fn stop(&self) {
+        self.inner
+            .channels
+            .lock()
+            .expect("frontbridge channel map poisoned")
+            .clear();
+        self.inner
+            .inbound
+            .lock()
+            .expect("frontbridge channel inbound poisoned")
+            .take();
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::{Duration, Instant},
+    };
+
+    use tauri::{
+        Emitter, Listener,
+        ipc::{Channel, InvokeResponseBody},
+        test::{MockRuntime, mock_app},
+    };
+
+    use super::ChannelTransport;
+    use crate::{
+        Builder, FrontBridge, FrontInvokeRequest, FrontInvokeResponse, FrontendGoneReason,
+        InvokeOptions, InvokeTarget, REQUEST_EVENT, RESPONSE_EVENT,
+    };
+
+    const ROUND_TRIPS: u32 = 10_000;
+
+    fn answer(request: &FrontInvokeRequest) -> FrontInvokeResponse {
+        FrontInvokeResponse {
+            id: request.id,
+            token: Some(request.token.clone()),
+            success: true,
+            data: None,
+            error: None,
+            chunk: false,
+            progress: false,
+        }
+    }
+
+    fn request(body: InvokeResponseBody) -> FrontInvokeRequest {
+        let InvokeResponseBody::Json(json) = body else {
+            panic!("channel messages are JSON");
+        };
+        serde_json::from_str(&json).unwrap()
+    }
+
+    /// Average time of `ROUND_TRIPS` sequential calls to the webview `bench`.
+    fn time_round_trips(bridge: &FrontBridge<MockRuntime>) -> Duration {
+        tauri::async_runtime::block_on(async {
+            let started = Instant::now();
+            for _ in 0..ROUND_TRIPS {
+                let options = InvokeOptions::new().target(InvokeTarget::webview("bench"));
+                bridge
+                    .invoke_by_name_with::<(), _>("ping", (), options)
+                    .await
+                    .unwrap();
+            }
+            started.elapsed() / ROUND_TRIPS
+        })
+    }
+
+    #[test]
+    fn a_gone_webview_loses_its_channel() {
+        let app = mock_app();
+        let transport = ChannelTransport::new();
+        let bridge = Builder::new()
+            .transport(transport.clone())
+            .build_bridge(app.handle());
+        let received = Arc::new(AtomicUsize::new(0));
+        let channel = Channel::new({
+            let received = Arc::clone(&received);
+            move |_| {
+                received.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+        });
+        transport.connect("child", channel);
+        let to_child = || InvokeOptions::new().target(InvokeTarget::webview("child"));
+
+        bridge.notify_with("toast", (), to_child()).unwrap();
+        assert_eq!(received.load(Ordering::Relaxed), 1);
+
+        bridge
+            .state
+            .frontend_gone("child", FrontendGoneReason::Navigated);
+        assert!(bridge.notify_with("toast", (), to_child()).is_err());
+        assert_eq!(received.load(Ordering::Relaxed), 1);
+        assert!(transport.inner.channels.lock().unwrap().is_empty());
+    }
+
+    /// Compares the backend half of a round trip over a channel and over `REQUEST_EVENT`:
+    /// serializing, routing and answering, with a Rust stand-in for the webview on the mock
+    /// runtime. The webview half has to be measured in a real app.
+    #[test]
+    #[ignore = "measurement; run with --release -- --ignored --nocapture"]
+    fn channel_and_event_round_trips() {
+        let app = mock_app();
+        let events = Builder::new().build_bridge(app.handle());
+        let handle = app.handle().clone();
+        app.listen_any(REQUEST_EVENT, move |event| {
+            let request: FrontInvokeRequest = serde_json::from_str(event.payload()).unwrap();
+            let handle = handle.clone();
+            // Answer outside the emit, as a webview would.
+            tauri::async_runtime::spawn(async move {
+                handle.emit(RESPONSE_EVENT, answer(&request)).unwrap();
+            });
+        });
+        let per_event = time_round_trips(&events);
+        events.shutdown();
+
+        let app = mock_app();
+        let transport = ChannelTransport::new();
+        let channels = Builder::new()
+            .transport(transport.clone())
+            .build_bridge(app.handle());
+        let state = Arc::clone(&channels.state);
+        let channel = Channel::new(move |body| {
+            let request = request(body);
+            let state = Arc::clone(&state);
+            tauri::async_runtime::spawn(async move {
+                state.resolve(answer(&request), Some("bench")).unwrap();
+            });
+            Ok(())
+        });
+        transport.connect("bench", channel);
+        let per_channel = time_round_trips(&channels);
+
+        println!("REQUEST_EVENT: {per_event:?} per call, channel: {per_channel:?} per call");
+    }
+}
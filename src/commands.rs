@@ -0,0 +1,40 @@
+use tauri::{Manager, Runtime, State, Webview, ipc::Channel};
+
+use crate::{
+    ChannelMessage, ChannelTransport, FrontBridge, FrontCall, FrontInvokeResponse, RejectReason,
+};
+
+#[tauri::command]
+pub(crate) fn connect<R: Runtime>(
+    webview: Webview<R>,
+    transport: State<'_, ChannelTransport>,
+    channel: Channel<ChannelMessage>,
+) {
+    transport.connect(webview.label(), channel);
+}
+
+/// The typed reply path: resolves the request `response` answers, provided `webview` is the
+/// one it was sent to. Resolves to `null` on success, or rejects with the `RejectReason`.
+#[tauri::command]
+pub(crate) fn respond<R: Runtime>(
+    webview: Webview<R>,
+    response: FrontInvokeResponse,
+) -> Result<(), RejectReason> {
+    match webview.try_state::<FrontBridge<R>>() {
+        Some(bridge) => bridge.state.resolve(response, Some(webview.label())),
+        None => Err(RejectReason::UnknownId),
+    }
+}
+
+/// Calls a handler registered with `FrontBridge::handle`. The reply arrives through the
+/// bridge's transport, addressed to `webview`.
+#[tauri::command]
+pub(crate) fn call<R: Runtime>(webview: Webview<R>, call: FrontCall) {
+    match webview.try_state::<FrontBridge<R>>() {
+        Some(bridge) => bridge.state.serve_call(webview.label().to_string(), call),
+        None => log::warn!(
+            "[frontbridge] call {} before the bridge was set up",
+            call.method
+        ),
+    }
+}
@@ -1,142 +1,71 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        Arc, Mutex,
-        atomic::{AtomicU64, Ordering},
-    },
+#[cfg(feature = "tauri")]
+mod bridge;
+#[cfg(feature = "tauri")]
+mod channel;
+#[cfg(feature = "tauri")]
+mod commands;
+#[cfg(feature = "tauri")]
+mod plugin;
+pub mod protocol;
+#[cfg(feature = "test-support")]
+pub mod testing;
+#[cfg(feature = "tauri")]
+mod transport;
+#[cfg(feature = "websocket")]
+mod websocket;
+
+#[cfg(feature = "websocket")]
+pub(crate) use protocol::request_token;
+pub use protocol::{
+    CANCEL_EVENT, CREDIT_EVENT, CancellationToken, ErrorPayload, FrontCall, FrontError,
+    FrontInvokeCancel, FrontInvokeCancelled, FrontInvokeCredit, FrontInvokeError,
+    FrontInvokeRequest, FrontInvokeResponse, FrontInvokeTimeout, FrontMethod, FrontReady,
+    FrontendGone, FrontendGoneReason, FrontendNotReady, HandlerRegistry, PendingTable,
+    ProgressCallback, READY_EVENT, REPLY_EVENT, REQUEST_EVENT, RESPONSE_EVENT, RejectReason,
+    RejectedResponse, Settlement, StreamOverflow,
 };
 
-use anyhow::{Context, Result, anyhow};
-use once_cell::sync::OnceCell;
-use serde::{Deserialize, Serialize, de::DeserializeOwned};
-use serde_json::Value;
-use tauri::{AppHandle, Emitter, Listener, Manager};
-use tokio::sync::oneshot;
-
-pub const REQUEST_EVENT: &str = "astrobox://frontinvoke/request";
-pub const RESPONSE_EVENT: &str = "astrobox://frontinvoke/response";
-
-#[derive(Debug, Serialize)]
-struct FrontInvokeRequest {
-    id: u64,
-    method: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    payload: Option<Value>,
-}
-
-#[derive(Debug, Deserialize)]
-struct FrontInvokeResponse {
-    id: u64,
-    success: bool,
-    #[serde(default)]
-    data: Option<Value>,
-    #[serde(default)]
-    error: Option<String>,
-}
-
-struct FrontInvokeState {
-    next_id: AtomicU64,
-    pending: Mutex<HashMap<u64, oneshot::Sender<FrontInvokeResponse>>>,
-}
-
-impl FrontInvokeState {
-    fn new() -> Self {
-        Self {
-            next_id: AtomicU64::new(1),
-            pending: Mutex::new(HashMap::new()),
-        }
-    }
-
-    fn register_listener(self: &Arc<Self>, app_handle: &AppHandle) {
-        let state = Arc::clone(self);
-        let _ = app_handle.listen_any(RESPONSE_EVENT, move |event| {
-            let payload = event.payload();
-            match serde_json::from_str::<FrontInvokeResponse>(payload) {
-                Ok(resp) => state.resolve(resp),
-                Err(err) => {
-                    log::error!("[frontbridge] failed to parse response payload: {err}");
-                }
-            }
-        });
-    }
-
-    fn resolve(&self, resp: FrontInvokeResponse) {
-        let sender = self
-            .pending
-            .lock()
-            .expect("frontbridge pending map poisoned")
-            .remove(&resp.id);
-        if let Some(tx) = sender {
-            let _ = tx.send(resp);
-        } else {
-            log::warn!(
-                "[frontbridge] no pending request for response id={}",
-                resp.id
-            );
-        }
-    }
-
-    fn add_pending(&self, id: u64, sender: oneshot::Sender<FrontInvokeResponse>) {
-        self.pending
-            .lock()
-            .expect("frontbridge pending map poisoned")
-            .insert(id, sender);
-    }
-}
-
-static FRONT_INVOKE_STATE: OnceCell<Arc<FrontInvokeState>> = OnceCell::new();
-
-fn state(app_handle: &AppHandle) -> Arc<FrontInvokeState> {
-    Arc::clone(FRONT_INVOKE_STATE.get_or_init(|| {
-        let state = Arc::new(FrontInvokeState::new());
-        state.register_listener(app_handle);
-        state
-    }))
-}
-
-pub async fn invoke_frontend<R, P>(
-    app_handle: &AppHandle,
-    method: impl Into<String>,
-    payload: P,
-) -> Result<R>
-where
-    R: DeserializeOwned,
-    P: Serialize,
-{
-    let method = method.into();
-    let state = state(app_handle);
-    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
-    let (tx, rx) = oneshot::channel();
-    state.add_pending(id, tx);
-
-    let payload_value = serde_json::to_value(payload).context("serialize frontend payload")?;
-    let request = FrontInvokeRequest {
-        id,
-        method: method.clone(),
-        payload: (!payload_value.is_null()).then_some(payload_value),
-    };
-
-    if let Some(window) = app_handle.get_webview_window("main") {
-        window
-            .emit(REQUEST_EVENT, &request)
-            .context("emit frontend invoke event (main)")?;
-    } else {
-        app_handle
-            .emit(REQUEST_EVENT, &request)
-            .context("emit frontend invoke event")?;
-    }
-
-    let resp = rx
-        .await
-        .map_err(|_| anyhow!("frontend invoke {method} dropped without response"))?;
-
-    if resp.success {
-        let value = resp.data.unwrap_or(Value::Null);
-        serde_json::from_value(value).context("deserialize frontend response")
-    } else {
-        Err(anyhow!(
-            "frontend invoke {method} failed: {}",
-            resp.error.unwrap_or_else(|| "unknown error".to_string())
-        ))
+#[cfg(feature = "tauri")]
+pub(crate) use bridge::{BridgeConfig, FrontInvokeState};
+#[cfg(feature = "tauri")]
+pub use bridge::{
+    DEFAULT_READY_TIMEOUT, DEFAULT_TIMEOUT, Destination, FrontBridge, Gathered, InvokeOptions,
+    InvokeTarget, READY_QUEUE_CAPACITY, RaceError, STREAM_CAPACITY, frontend_ready,
+    gather_frontend, handle_page_load, invoke_frontend, invoke_frontend_method,
+    invoke_frontend_method_with, invoke_frontend_stream, invoke_frontend_stream_with,
+    invoke_frontend_typed, invoke_frontend_typed_with, invoke_frontend_with, notify_frontend,
+    notify_frontend_with, pending_count, race_frontend,
+};
+#[cfg(feature = "tauri")]
+pub use channel::{ChannelMessage, ChannelTransport};
+#[cfg(feature = "tauri")]
+pub use frontbridge_macros::interface;
+#[cfg(feature = "tauri")]
+pub use plugin::{Builder, init};
+#[cfg(feature = "tauri")]
+pub use transport::{EventTransport, Inbound, Transport};
+#[cfg(feature = "websocket")]
+pub use websocket::WebSocketTransport;
+
+/// Support for code generated by `#[interface]`; not a stable API.
+#[cfg(feature = "tauri")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+    pub use tauri;
+
+    use crate::FrontInvokeError;
+
+    pub fn to_param<T: serde::Serialize + ?Sized>(
+        method: &str,
+        name: &str,
+        value: &T,
+    ) -> Result<serde_json::Value, FrontInvokeError> {
+        serde_json::to_value(value).map_err(|err| {
+            FrontInvokeError::transport(
+                method,
+                anyhow::Error::new(err).context(format!("serialize argument `{name}`")),
+            )
+        })
     }
 }
@@ -0,0 +1,181 @@
+use std::{sync::Arc, time::Duration};
+
+use tauri::{
+    AppHandle, Manager, Runtime,
+    plugin::{self, TauriPlugin},
+};
+
+use crate::{
+    BridgeConfig, ChannelTransport, FrontBridge, InvokeTarget, RejectedResponse, Transport,
+    commands, handle_page_load,
+};
+
+/// The bridge as a Tauri plugin with the default settings.
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new().build()
+}
+
+/// Configures the `frontbridge` plugin.
+///
+/// ```ignore
+/// tauri::Builder::default()
+///     .plugin(frontbridge::Builder::new().default_target(InvokeTarget::window("main")).build())
+/// ```
+#[derive(Default)]
+pub struct Builder {
+    config: BridgeConfig,
+    channel: Option<ChannelTransport>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request_event(mut self, event: impl Into<String>) -> Self {
+        self.config.request_event = event.into();
+        self
+    }
+
+    pub fn response_event(mut self, event: impl Into<String>) -> Self {
+        self.config.response_event = event.into();
+        self
+    }
+
+    pub fn cancel_event(mut self, event: impl Into<String>) -> Self {
+        self.config.cancel_event = event.into();
+        self
+    }
+
+    pub fn credit_event(mut self, event: impl Into<String>) -> Self {
+        self.config.credit_event = event.into();
+        self
+    }
+
+    pub fn ready_event(mut self, event: impl Into<String>) -> Self {
+        self.config.ready_event = event.into();
+        self
+    }
+
+    pub fn reply_event(mut self, event: impl Into<String>) -> Self {
+        self.config.reply_event = event.into();
+        self
+    }
+
+    /// Where calls go when their `InvokeOptions` don't name a target.
+    pub fn default_target(mut self, target: impl Into<InvokeTarget>) -> Self {
+        self.config.default_target = target.into();
+        self
+    }
+
+    /// The deadline for calls that don't set their own; `DEFAULT_TIMEOUT` unless changed.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.config.default_timeout = Some(timeout);
+        self
+    }
+
+    pub fn no_default_timeout(mut self) -> Self {
+        self.config.default_timeout = None;
+        self
+    }
+
+    /// How long a request may wait for its target's `READY_EVENT`; `DEFAULT_READY_TIMEOUT`
+    /// unless changed.
+    pub fn ready_timeout(mut self, timeout: Duration) -> Self {
+        self.config.ready_timeout = timeout;
+        self
+    }
+
+    /// Whether requests to a labelled webview wait for its `READY_EVENT`. Off by default:
+    /// requests are sent straight away and are lost if the page isn't listening yet. Turn it
+    /// on once the frontend announces itself on every page load.
+    pub fn wait_for_ready(mut self, enabled: bool) -> Self {
+        self.config.wait_for_ready = enabled;
+        self
+    }
+
+    pub fn ready_queue_capacity(mut self, capacity: usize) -> Self {
+        self.config.ready_queue_capacity = capacity;
+        self
+    }
+
+    /// How many chunks a streaming call buffers. The frontend gets that much credit up front
+    /// and more as the stream is consumed; a chunk beyond it fails the stream with
+    /// `StreamOverflow`.
+    pub fn stream_capacity(mut self, capacity: usize) -> Self {
+        self.config.stream_capacity = capacity;
+        self
+    }
+
+    /// Logs every request sent and response received at debug level.
+    pub fn log_traffic(mut self, enabled: bool) -> Self {
+        self.config.log_traffic = enabled;
+        self
+    }
+
+    /// Replaces the default warning log for rejected responses.
+    pub fn security_hook(
+        mut self,
+        hook: impl Fn(&RejectedResponse) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.security_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Carries requests over `transport` instead of Tauri events; the event names are then unused.
+    pub fn transport(mut self, transport: impl Transport) -> Self {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Uses a `ChannelTransport`: webviews connect with `plugin:frontbridge|connect`.
+    /// Connecting announces the webview, so this also turns on `wait_for_ready`.
+    pub fn channel_transport(mut self) -> Self {
+        let transport = ChannelTransport::new();
+        self.config.transport = Some(Arc::new(transport.clone()));
+        self.config.wait_for_ready = true;
+        self.channel = Some(transport);
+        self
+    }
+
+    /// Creates a bridge with these settings without registering the plugin, e.g. in tests or
+    /// for a second bridge. It isn't managed, so the free `invoke_frontend*` functions don't
+    /// use it unless you `manage` it before they first run.
+    pub fn build_bridge<R: Runtime>(self, app_handle: &AppHandle<R>) -> FrontBridge<R> {
+        FrontBridge::with_config(app_handle, self.config)
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        let (config, channel) = (self.config, self.channel);
+        plugin::Builder::new("frontbridge")
+            .invoke_handler(tauri::generate_handler![
+                commands::connect,
+                commands::respond,
+                commands::call
+            ])
+            .setup(move |app, _api| {
+                if let Some(channel) = channel {
+                    app.manage(channel);
+                }
+                let bridge = FrontBridge::with_config(app.app_handle(), config);
+                if !app.manage(bridge.clone()) {
+                    bridge.shutdown();
+                    log::warn!("[frontbridge] a bridge was already managed, keeping it");
+                }
+                Ok(())
+            })
+            .on_page_load(handle_page_load)
+            .on_webview_ready(|webview| {
+                if let Some(bridge) = webview.try_state::<FrontBridge<R>>() {
+                    bridge.state.webview_created(webview.label());
+                    bridge.watch_webview(&webview);
+                }
+            })
+            .on_drop(|app| {
+                if let Some(bridge) = app.try_state::<FrontBridge<R>>() {
+                    bridge.shutdown();
+                }
+            })
+            .build()
+    }
+}
@@ -0,0 +1,1371 @@
+//! The wire protocol and pending-request bookkeeping, free of any Tauri types.
+//!
+//! Everything here builds with `default-features = false`, so a headless client or test
+//! harness can speak the protocol without depending on `tauri`.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tokio::sync::{Notify, mpsc, oneshot};
+
+pub const REQUEST_EVENT: &str = "astrobox://frontinvoke/request";
+pub const RESPONSE_EVENT: &str = "astrobox://frontinvoke/response";
+pub const CANCEL_EVENT: &str = "astrobox://frontinvoke/cancel";
+/// Grants a stream's frontend more chunks as the backend consumes them.
+pub const CREDIT_EVENT: &str = "astrobox://frontinvoke/credit";
+pub const READY_EVENT: &str = "astrobox://frontinvoke/ready";
+/// Replies to frontend-initiated calls, which arrive through the plugin's `call` command.
+pub const REPLY_EVENT: &str = "astrobox://frontinvoke/reply";
+
+/// How many recently settled ids are remembered to tell late duplicates from unknown ids.
+const SETTLED_HISTORY: usize = 256;
+
+/// Why a response was refused by the bridge. Returned as the error of the `respond` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectReason {
+    /// No request with this id is in flight; usually a late answer to a timed-out call.
+    UnknownId,
+    /// The request was already settled by an earlier response. Not reported for broadcasts,
+    /// which every webview may answer: late answers carrying their token are accepted silently.
+    AlreadyResolved,
+    /// The response didn't carry the token sent with its request, which is in flight or was a
+    /// recently settled broadcast.
+    TokenMismatch,
+    /// The response came from a webview other than the one the request was sent to.
+    WrongOrigin,
+    /// A chunk arrived for a request that wasn't made as a stream.
+    NotStreaming,
+    /// A chunk arrived without credit for it: the frontend sent more than the request's
+    /// `credit` plus the `FrontInvokeCredit`s granted since. The chunk was refused and the
+    /// stream failed with `StreamOverflow`; stop sending, a cancel for the request follows.
+    StreamFull,
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownId => f.write_str("no pending request"),
+            Self::AlreadyResolved => f.write_str("already resolved"),
+            Self::TokenMismatch => f.write_str("foreign token"),
+            Self::WrongOrigin => f.write_str("wrong origin webview"),
+            Self::NotStreaming => f.write_str("chunk for a non-streaming request"),
+            Self::StreamFull => f.write_str("stream buffer full"),
+        }
+    }
+}
+
+/// Passed to the security hook for every response that couldn't resolve a request.
+#[derive(Debug, Clone)]
+pub struct RejectedResponse {
+    pub id: u64,
+    /// Method of the request the response tried to resolve, if it is in flight or recently
+    /// settled.
+    pub method: Option<String>,
+    /// Label of the webview that sent the response, when the transport knows it.
+    pub origin: Option<String>,
+    pub reason: RejectReason,
+}
+
+/// 128 bits from the OS RNG, hex encoded.
+pub(crate) fn request_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("generate request token: {err}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+#[derive(Debug, Default)]
+struct CancellationInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Lets a caller abandon an in-flight invoke; the frontend is told via `CANCEL_EVENT`.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationInner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Carried by `FrontInvokeError::Timeout` when the frontend doesn't answer in time.
+#[derive(Debug, Clone)]
+pub struct FrontInvokeTimeout {
+    pub method: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for FrontInvokeTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "frontend invoke {} timed out after {:?}",
+            self.method, self.timeout
+        )
+    }
+}
+
+impl std::error::Error for FrontInvokeTimeout {}
+
+/// Carried by `FrontInvokeError::Cancelled` when the call's `CancellationToken` fires first.
+#[derive(Debug, Clone)]
+pub struct FrontInvokeCancelled {
+    pub method: String,
+}
+
+impl std::fmt::Display for FrontInvokeCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frontend invoke {} was cancelled", self.method)
+    }
+}
+
+impl std::error::Error for FrontInvokeCancelled {}
+
+/// The source of a `FrontInvokeError::Transport` when the target window never announced
+/// itself via `READY_EVENT`, or its queue of early requests is full. Once the error is in an
+/// `anyhow::Error`, find it with `err.chain().find_map(|e| e.downcast_ref::<FrontendNotReady>())`.
+#[derive(Debug, Clone)]
+pub struct FrontendNotReady {
+    pub method: String,
+    pub label: String,
+}
+
+impl std::fmt::Display for FrontendNotReady {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "frontend {} not ready for invoke {}",
+            self.label, self.method
+        )
+    }
+}
+
+impl std::error::Error for FrontendNotReady {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontendGoneReason {
+    /// The window was closed; retrying against the same label will not succeed.
+    Destroyed,
+    /// The page reloaded or navigated away; the new page may answer a retry once it is ready.
+    Navigated,
+}
+
+impl std::fmt::Display for FrontendGoneReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Destroyed => f.write_str("destroyed"),
+            Self::Navigated => f.write_str("navigated"),
+        }
+    }
+}
+
+/// The source of a `FrontInvokeError::Transport` when the webview a request was addressed to
+/// is destroyed, reloads or navigates before answering. Reachable through `source()`, like
+/// `FrontendNotReady`.
+#[derive(Debug, Clone)]
+pub struct FrontendGone {
+    pub method: String,
+    pub label: String,
+    pub reason: FrontendGoneReason,
+}
+
+impl FrontendGone {
+    pub fn is_retryable(&self) -> bool {
+        self.reason == FrontendGoneReason::Navigated
+    }
+}
+
+impl std::fmt::Display for FrontendGone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "frontend {} went away ({}) during invoke {}",
+            self.label, self.reason, self.method
+        )
+    }
+}
+
+impl std::error::Error for FrontendGone {}
+
+/// The source of a `FrontInvokeError::Transport` that ends a stream whose frontend sent more
+/// chunks than it had credit for, overrunning the `capacity`-chunk buffer. A frontend that
+/// honours `FrontInvokeCredit` never causes it.
+#[derive(Debug, Clone)]
+pub struct StreamOverflow {
+    pub method: String,
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for StreamOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "frontend stream {} overflowed its {}-chunk buffer",
+            self.method, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for StreamOverflow {}
+
+/// What failed on the other side of a call, as carried in `FrontInvokeResponse::error`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ErrorRepr")]
+pub struct ErrorPayload {
+    /// Machine-readable reason, e.g. `"userCancelled"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    /// The JavaScript stack, when the frontend includes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+}
+
+impl ErrorPayload {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            code: None,
+            message: message.into(),
+            details: None,
+            stack: None,
+        }
+    }
+
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Decodes the payload as an application error type. `E` sees the serialized payload, so
+    /// an enum tagged with `#[serde(tag = "code", content = "details")]` matches on `code`.
+    pub fn decode<E: DeserializeOwned>(&self) -> serde_json::Result<E> {
+        serde_json::to_value(self).and_then(serde_json::from_value)
+    }
+}
+
+impl std::fmt::Display for ErrorPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} ({code})", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ErrorPayload {}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorRepr {
+    Message(String),
+    Full {
+        #[serde(default)]
+        code: Option<String>,
+        message: String,
+        #[serde(default)]
+        details: Option<Value>,
+        #[serde(default)]
+        stack: Option<String>,
+    },
+}
+
+impl From<ErrorRepr> for ErrorPayload {
+    fn from(repr: ErrorRepr) -> Self {
+        match repr {
+            ErrorRepr::Message(message) => Self::new(message),
+            ErrorRepr::Full {
+                code,
+                message,
+                details,
+                stack,
+            } => Self {
+                code,
+                message,
+                details,
+                stack,
+            },
+        }
+    }
+}
+
+/// Why a call to the frontend failed. Converts into `anyhow::Error` like any error; the
+/// transport and decode causes are its `source()`, so print it with `{:#}` once converted.
+#[derive(Debug)]
+pub enum FrontInvokeError {
+    /// The request never got an answer through no fault of the frontend's code: the payload
+    /// didn't serialize, the target didn't exist, wasn't ready (`FrontendNotReady`) or went
+    /// away (`FrontendGone`), a stream overflowed (`StreamOverflow`), the transport failed,
+    /// or the bridge shut down. Downcast `source` for the specifics.
+    Transport {
+        method: String,
+        source: anyhow::Error,
+    },
+    Timeout(FrontInvokeTimeout),
+    Cancelled(FrontInvokeCancelled),
+    /// The frontend answered with an error.
+    Frontend {
+        method: String,
+        error: Box<ErrorPayload>,
+    },
+    /// The frontend answered, but its data didn't fit the expected type.
+    Decode {
+        method: String,
+        source: serde_json::Error,
+    },
+}
+
+impl FrontInvokeError {
+    pub fn transport(method: impl Into<String>, source: impl Into<anyhow::Error>) -> Self {
+        Self::Transport {
+            method: method.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        match self {
+            Self::Transport { method, .. }
+            | Self::Frontend { method, .. }
+            | Self::Decode { method, .. } => method,
+            Self::Timeout(timeout) => &timeout.method,
+            Self::Cancelled(cancelled) => &cancelled.method,
+        }
+    }
+
+    /// Whether the webview went away in a way a retry may recover from; see
+    /// `FrontendGone::is_retryable`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport { source, .. } => source
+                .downcast_ref::<FrontendGone>()
+                .is_some_and(FrontendGone::is_retryable),
+            _ => false,
+        }
+    }
+
+    /// The frontend's error, when it reported one.
+    pub fn payload(&self) -> Option<&ErrorPayload> {
+        match self {
+            Self::Frontend { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FrontInvokeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transport { method, .. } => write!(f, "frontend invoke {method} failed"),
+            Self::Timeout(timeout) => write!(f, "{timeout}"),
+            Self::Cancelled(cancelled) => write!(f, "{cancelled}"),
+            Self::Frontend { method, error } => {
+                write!(f, "frontend invoke {method} failed: {error}")
+            }
+            Self::Decode { method, .. } => {
+                write!(f, "deserialize frontend response to {method}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrontInvokeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport { source, .. } => Some(source.as_ref()),
+            Self::Decode { source, .. } => Some(source),
+            Self::Timeout(_) | Self::Cancelled(_) | Self::Frontend { .. } => None,
+        }
+    }
+}
+
+impl From<FrontInvokeTimeout> for FrontInvokeError {
+    fn from(timeout: FrontInvokeTimeout) -> Self {
+        Self::Timeout(timeout)
+    }
+}
+
+impl From<FrontInvokeCancelled> for FrontInvokeError {
+    fn from(cancelled: FrontInvokeCancelled) -> Self {
+        Self::Cancelled(cancelled)
+    }
+}
+
+/// A frontend method declared once, so call sites get its name and types from the compiler.
+///
+/// ```ignore
+/// struct Confirm;
+///
+/// impl FrontMethod for Confirm {
+///     const NAME: &'static str = "confirm";
+///     type Params = ConfirmParams;
+///     type Output = bool;
+/// }
+///
+/// let accepted = bridge.invoke::<Confirm>(ConfirmParams { title }).await?;
+/// ```
+pub trait FrontMethod {
+    /// The method name the frontend registers its handler under.
+    const NAME: &'static str;
+    type Params: Serialize;
+    type Output: DeserializeOwned;
+}
+
+/// The error of a typed invoke: either the frontend's error decoded as `E`, or any other
+/// failure of the call.
+///
+/// ```ignore
+/// #[derive(Debug, Deserialize)]
+/// #[serde(tag = "code", content = "details", rename_all = "camelCase")]
+/// enum PickError { UserCancelled, Denied { reason: String } }
+///
+/// match bridge.invoke_typed::<PathBuf, PickError, _>("pickFile", ()).await {
+///     Err(FrontError::App(PickError::UserCancelled)) => {}
+///     ...
+/// }
+/// ```
+#[derive(Debug)]
+pub enum FrontError<E> {
+    /// The frontend reported an error that decoded as `E`.
+    App(E),
+    /// Any other failure, including frontend errors that don't decode as `E`.
+    Invoke(FrontInvokeError),
+}
+
+impl<E: DeserializeOwned> From<FrontInvokeError> for FrontError<E> {
+    fn from(err: FrontInvokeError) -> Self {
+        match err.payload().map(ErrorPayload::decode) {
+            Some(Ok(app)) => Self::App(app),
+            _ => Self::Invoke(err),
+        }
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for FrontError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::App(err) => write!(f, "{err}"),
+            Self::Invoke(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for FrontError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::App(_) => None,
+            Self::Invoke(err) => err.source(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontInvokeRequest {
+    pub id: u64,
+    /// Echoed back in the response, so only webviews the request was delivered to can answer
+    /// it: the addressed webview, or every webview for a broadcast.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Value>,
+    /// Set on notifications, which have no id (`0`) or token and must not be answered.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notify: bool,
+    /// Set when the caller accepts chunk responses before the final one.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    /// How many chunks a stream may send before waiting for a `FrontInvokeCredit`; set on
+    /// streams only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit: Option<u32>,
+}
+
+impl FrontInvokeRequest {
+    /// A fire-and-forget request; nothing waits for, or accepts, a reply to it.
+    pub fn notification(method: impl Into<String>, payload: Option<Value>) -> Self {
+        Self {
+            id: 0,
+            token: String::new(),
+            method: method.into(),
+            payload: payload.filter(|payload| !payload.is_null()),
+            notify: true,
+            stream: false,
+            credit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontInvokeCancel {
+    pub id: u64,
+}
+
+/// Lets the frontend send `credit` more chunks of the stream `id`, granted as the backend
+/// consumes the ones it buffered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontInvokeCredit {
+    pub id: u64,
+    pub credit: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontReady {
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontInvokeResponse {
+    pub id: u64,
+    #[serde(default)]
+    pub token: Option<String>,
+    pub success: bool,
+    #[serde(default)]
+    pub data: Option<Value>,
+    /// Why the call failed. A bare string from older frontends is read as the message.
+    #[serde(default)]
+    pub error: Option<ErrorPayload>,
+    /// One item of a streamed answer; the request stays pending until a response without
+    /// this flag ends the stream (or fails it, when `success` is false).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub chunk: bool,
+    /// A progress update carried in `data`; the request stays pending and the update goes to
+    /// the caller's progress callback, if any.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub progress: bool,
+}
+
+/// A request from the frontend to a handler registered on the bridge, answered with a
+/// `FrontInvokeResponse` carrying the same id. Ids are chosen by the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontCall {
+    pub id: u64,
+    pub method: String,
+    #[serde(default)]
+    pub payload: Option<Value>,
+}
+
+impl FrontInvokeResponse {
+    /// Decodes the data of a successful response, or turns the frontend's error into one.
+    pub fn into_result<T: DeserializeOwned>(
+        self,
+        method: &str,
+    ) -> std::result::Result<T, FrontInvokeError> {
+        if self.success {
+            let value = self.data.unwrap_or(Value::Null);
+            serde_json::from_value(value).map_err(|source| FrontInvokeError::Decode {
+                method: method.to_string(),
+                source,
+            })
+        } else {
+            Err(FrontInvokeError::Frontend {
+                method: method.to_string(),
+                error: Box::new(
+                    self.error
+                        .unwrap_or_else(|| ErrorPayload::new("unknown error")),
+                ),
+            })
+        }
+    }
+}
+
+/// Receives the `data` of every progress update for one request.
+pub type ProgressCallback = Arc<dyn Fn(Value) + Send + Sync>;
+
+/// What a pending request is settled with: the frontend's answer, or why none will come
+/// (its webview went away, or its stream overflowed).
+pub type Settlement = std::result::Result<FrontInvokeResponse, FrontInvokeError>;
+
+/// A recently settled request, remembered to judge late answers to it.
+#[derive(Clone)]
+struct SettledEntry {
+    id: u64,
+    method: String,
+    /// The request's token if it was a broadcast, whose late answers are still genuine.
+    broadcast_token: Option<String>,
+}
+
+struct PendingEntry {
+    method: String,
+    token: String,
+    /// Webview the request was addressed to; `None` for broadcasts.
+    label: Option<String>,
+    sender: oneshot::Sender<Settlement>,
+    /// Where chunk responses go for streaming requests. Dropped with the entry, which
+    /// tells the consumer that the final response is next.
+    chunks: Option<mpsc::Sender<Value>>,
+    progress: Option<ProgressCallback>,
+}
+
+/// Allocates request ids and tokens and matches responses back to their callers.
+///
+/// The Tauri bridge keeps one per app; other hosts of the protocol can drive one directly:
+/// send the request `PendingTable::request` returns, feed every incoming response to
+/// `PendingTable::resolve`, and await the receiver.
+pub struct PendingTable {
+    next_id: AtomicU64,
+    entries: Mutex<HashMap<u64, PendingEntry>>,
+    settled: Mutex<VecDeque<SettledEntry>>,
+    log_traffic: bool,
+}
+
+impl Default for PendingTable {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            entries: Mutex::new(HashMap::new()),
+            settled: Mutex::new(VecDeque::with_capacity(SETTLED_HISTORY)),
+            log_traffic: false,
+        }
+    }
+}
+
+impl PendingTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs every settled request at debug level.
+    pub fn log_traffic(mut self, enabled: bool) -> Self {
+        self.log_traffic = enabled;
+        self
+    }
+
+    /// Registers a new request for `method`, addressed to the webview `label` if known.
+    pub fn request(
+        &self,
+        method: impl Into<String>,
+        payload: Option<Value>,
+        label: Option<String>,
+    ) -> Result<(FrontInvokeRequest, oneshot::Receiver<Settlement>)> {
+        self.register(method.into(), payload, label, None)
+    }
+
+    /// Like `request`, but the frontend may answer with chunks first. The request grants
+    /// `capacity` chunks of credit, which are buffered until the receiver takes them; grant
+    /// more with a `FrontInvokeCredit` as it does. A chunk beyond the credit is rejected with
+    /// `RejectReason::StreamFull` and settles the request with `StreamOverflow`.
+    pub fn request_stream(
+        &self,
+        method: impl Into<String>,
+        payload: Option<Value>,
+        label: Option<String>,
+        capacity: usize,
+    ) -> Result<(
+        FrontInvokeRequest,
+        mpsc::Receiver<Value>,
+        oneshot::Receiver<Settlement>,
+    )> {
+        let capacity = capacity.clamp(1, u32::MAX as usize);
+        let (chunks, chunk_rx) = mpsc::channel(capacity);
+        let (mut request, rx) = self.register(method.into(), payload, label, Some(chunks))?;
+        request.credit = Some(capacity as u32);
+        Ok((request, chunk_rx, rx))
+    }
+
+    fn register(
+        &self,
+        method: String,
+        payload: Option<Value>,
+        label: Option<String>,
+        chunks: Option<mpsc::Sender<Value>>,
+    ) -> Result<(FrontInvokeRequest, oneshot::Receiver<Settlement>)> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = request_token()?;
+        let (sender, rx) = oneshot::channel();
+        let stream = chunks.is_some();
+        self.entries
+            .lock()
+            .expect("frontbridge pending map poisoned")
+            .insert(
+                id,
+                PendingEntry {
+                    method: method.clone(),
+                    token: token.clone(),
+                    label,
+                    sender,
+                    chunks,
+                    progress: None,
+                },
+            );
+        let request = FrontInvokeRequest {
+            id,
+            token,
+            method,
+            payload: payload.filter(|payload| !payload.is_null()),
+            notify: false,
+            stream,
+            credit: None,
+        };
+        Ok((request, rx))
+    }
+
+    /// Routes progress updates for the pending request `id` to `callback`. Returns `false` if
+    /// the request isn't pending.
+    pub fn on_progress(&self, id: u64, callback: ProgressCallback) -> bool {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("frontbridge pending map poisoned");
+        match entries.get_mut(&id) {
+            Some(entry) => {
+                entry.progress = Some(callback);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Settles the request `resp` answers, provided it carries that request's token and, when
+    /// the sender is known, came from the webview the request was addressed to.
+    /// A rejected response leaves the request pending for the genuine answer.
+    pub fn resolve(
+        &self,
+        resp: FrontInvokeResponse,
+        origin: Option<&str>,
+    ) -> std::result::Result<(), RejectedResponse> {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("frontbridge pending map poisoned");
+        let (reason, method) = match entries.get(&resp.id) {
+            None => match self.settled(resp.id) {
+                // Every webview got the broadcast; answers after the first are expected.
+                Some(SettledEntry {
+                    broadcast_token: Some(token),
+                    method,
+                    ..
+                }) if resp.token.as_deref() == Some(token.as_str()) => {
+                    if self.log_traffic {
+                        log::debug!("[frontbridge] <- {method} id={} late answer", resp.id);
+                    }
+                    return Ok(());
+                }
+                Some(SettledEntry {
+                    broadcast_token: Some(_),
+                    method,
+                    ..
+                }) => (RejectReason::TokenMismatch, Some(method)),
+                Some(settled) => (RejectReason::AlreadyResolved, Some(settled.method)),
+                None => (RejectReason::UnknownId, None),
+            },
+            Some(entry) if resp.token.as_deref() != Some(entry.token.as_str()) => {
+                (RejectReason::TokenMismatch, Some(entry.method.clone()))
+            }
+            Some(entry)
+                if origin.is_some()
+                    && entry.label.is_some()
+                    && entry.label.as_deref() != origin =>
+            {
+                (RejectReason::WrongOrigin, Some(entry.method.clone()))
+            }
+            Some(entry) if resp.progress => {
+                if self.log_traffic {
+                    log::debug!("[frontbridge] <- {} id={} progress", entry.method, resp.id);
+                }
+                let callback = entry.progress.clone();
+                drop(entries);
+                if let Some(callback) = callback {
+                    callback(resp.data.unwrap_or(Value::Null));
+                }
+                return Ok(());
+            }
+            Some(entry) if resp.chunk => {
+                let Some(chunks) = &entry.chunks else {
+                    return Err(RejectedResponse {
+                        id: resp.id,
+                        method: Some(entry.method.clone()),
+                        origin: origin.map(str::to_string),
+                        reason: RejectReason::NotStreaming,
+                    });
+                };
+                let capacity = chunks.max_capacity();
+                match chunks.try_send(resp.data.unwrap_or(Value::Null)) {
+                    Ok(()) => {
+                        if self.log_traffic {
+                            log::debug!("[frontbridge] <- {} id={} chunk", entry.method, resp.id);
+                        }
+                        return Ok(());
+                    }
+                    // The consumer dropped the stream; the guard is about to cancel it.
+                    Err(mpsc::error::TrySendError::Closed(_)) => return Ok(()),
+                    Err(mpsc::error::TrySendError::Full(_)) => {}
+                }
+                let entry = entries.remove(&resp.id).expect("pending entry vanished");
+                drop(entries);
+                self.record_settled(resp.id, &entry);
+                log::warn!(
+                    "[frontbridge] stream {} id={} sent a chunk beyond its {capacity}-chunk credit",
+                    entry.method,
+                    resp.id
+                );
+                let overflow = StreamOverflow {
+                    method: entry.method.clone(),
+                    capacity,
+                };
+                let _ = entry.sender.send(Err(FrontInvokeError::transport(
+                    entry.method.clone(),
+                    overflow,
+                )));
+                (RejectReason::StreamFull, Some(entry.method))
+            }
+            Some(_) => {
+                let entry = entries.remove(&resp.id).expect("pending entry vanished");
+                drop(entries);
+                self.record_settled(resp.id, &entry);
+                if self.log_traffic {
+                    log::debug!(
+                        "[frontbridge] <- {} id={} success={}",
+                        entry.method,
+                        resp.id,
+                        resp.success
+                    );
+                }
+                let _ = entry.sender.send(Ok(resp));
+                return Ok(());
+            }
+        };
+        Err(RejectedResponse {
+            id: resp.id,
+            method,
+            origin: origin.map(str::to_string),
+            reason,
+        })
+    }
+
+    /// Settles every request addressed to `label` with `FrontendGone`, except the ids in
+    /// `keep`. Returns how many were failed.
+    pub fn fail_label(&self, label: &str, reason: FrontendGoneReason, keep: &[u64]) -> usize {
+        let gone = {
+            let mut entries = self
+                .entries
+                .lock()
+                .expect("frontbridge pending map poisoned");
+            let ids: Vec<u64> = entries
+                .iter()
+                .filter(|(id, entry)| entry.label.as_deref() == Some(label) && !keep.contains(id))
+                .map(|(id, _)| *id)
+                .collect();
+            ids.into_iter()
+                .filter_map(|id| entries.remove(&id))
+                .collect::<Vec<_>>()
+        };
+        let count = gone.len();
+        for entry in gone {
+            let gone = FrontendGone {
+                method: entry.method.clone(),
+                label: label.to_string(),
+                reason,
+            };
+            let _ = entry
+                .sender
+                .send(Err(FrontInvokeError::transport(entry.method, gone)));
+        }
+        count
+    }
+
+    /// Forgets the request `id`; its receiver sees the sender dropped.
+    pub fn remove(&self, id: u64) -> bool {
+        self.entries
+            .lock()
+            .expect("frontbridge pending map poisoned")
+            .remove(&id)
+            .is_some()
+    }
+
+    /// Forgets every pending request.
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .expect("frontbridge pending map poisoned")
+            .clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("frontbridge pending map poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn record_settled(&self, id: u64, entry: &PendingEntry) {
+        let mut settled = self
+            .settled
+            .lock()
+            .expect("frontbridge settled history poisoned");
+        if settled.len() == SETTLED_HISTORY {
+            settled.pop_front();
+        }
+        settled.push_back(SettledEntry {
+            id,
+            method: entry.method.clone(),
+            broadcast_token: entry.label.is_none().then(|| entry.token.clone()),
+        });
+    }
+
+    fn settled(&self, id: u64) -> Option<SettledEntry> {
+        self.settled
+            .lock()
+            .expect("frontbridge settled history poisoned")
+            .iter()
+            .find(|settled| settled.id == id)
+            .cloned()
+    }
+}
+
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+type Handler = Arc<dyn Fn(Value) -> HandlerFuture + Send + Sync>;
+
+/// Backend methods the frontend can call, keyed by method name.
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: RwLock<HashMap<String, Handler>>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `method` with `handler`, replacing any previous handler for it. A missing
+    /// payload is decoded from `null`.
+    pub fn handle<P, T, F, Fut>(&self, method: impl Into<String>, handler: F)
+    where
+        P: DeserializeOwned,
+        T: Serialize,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |payload| {
+            let call = serde_json::from_value(payload)
+                .context("deserialize call payload")
+                .map(&handler);
+            Box::pin(async move {
+                let output = call?.await?;
+                serde_json::to_value(output).context("serialize call result")
+            })
+        });
+        self.handlers
+            .write()
+            .expect("frontbridge handler registry poisoned")
+            .insert(method.into(), handler);
+    }
+
+    /// Returns `false` if nothing was registered for `method`.
+    pub fn remove(&self, method: &str) -> bool {
+        self.handlers
+            .write()
+            .expect("frontbridge handler registry poisoned")
+            .remove(method)
+            .is_some()
+    }
+
+    pub fn contains(&self, method: &str) -> bool {
+        self.handlers
+            .read()
+            .expect("frontbridge handler registry poisoned")
+            .contains_key(method)
+    }
+
+    /// Runs the handler for `call` and turns its outcome into the reply.
+    pub async fn call(&self, call: FrontCall) -> FrontInvokeResponse {
+        let handler = self
+            .handlers
+            .read()
+            .expect("frontbridge handler registry poisoned")
+            .get(&call.method)
+            .cloned();
+        let result = match handler {
+            Some(handler) => handler(call.payload.unwrap_or(Value::Null)).await,
+            None => Err(anyhow!("no handler for {}", call.method)),
+        };
+        // Handlers can fail with an `ErrorPayload` to send a code and details along.
+        let error = |err: anyhow::Error| match err.downcast::<ErrorPayload>() {
+            Ok(payload) => payload,
+            Err(err) => ErrorPayload::new(format!("{err:#}")),
+        };
+        match result {
+            Ok(data) => FrontInvokeResponse {
+                id: call.id,
+                token: None,
+                success: true,
+                data: Some(data),
+                error: None,
+                chunk: false,
+                progress: false,
+            },
+            Err(err) => FrontInvokeResponse {
+                id: call.id,
+                token: None,
+                success: false,
+                data: None,
+                error: Some(error(err)),
+                chunk: false,
+                progress: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn answer(request: &FrontInvokeRequest, data: Value) -> FrontInvokeResponse {
+        FrontInvokeResponse {
+            id: request.id,
+            token: Some(request.token.clone()),
+            success: true,
+            data: Some(data),
+            error: None,
+            chunk: false,
+            progress: false,
+        }
+    }
+
+    fn settled(rx: &mut oneshot::Receiver<Settlement>) -> Settlement {
+        rx.try_recv().expect("request not settled")
+    }
+
+    fn rejection(
+        table: &PendingTable,
+        resp: FrontInvokeResponse,
+        origin: Option<&str>,
+    ) -> RejectReason {
+        table.resolve(resp, origin).unwrap_err().reason
+    }
+
+    #[test]
+    fn resolve_settles_the_matching_request() {
+        let table = PendingTable::new();
+        let (request, mut rx) = table.request("add", None, Some("main".into())).unwrap();
+        table
+            .resolve(answer(&request, json!(3)), Some("main"))
+            .unwrap();
+        let value: u32 = settled(&mut rx).unwrap().into_result("add").unwrap();
+        assert_eq!(value, 3);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn resolve_rejects_a_foreign_token() {
+        let table = PendingTable::new();
+        let (request, mut rx) = table.request("add", None, Some("main".into())).unwrap();
+        let forged = FrontInvokeResponse {
+            token: Some("forged".into()),
+            ..answer(&request, json!(0))
+        };
+        let rejected = table.resolve(forged, Some("main")).unwrap_err();
+        assert_eq!(rejected.reason, RejectReason::TokenMismatch);
+        assert_eq!(rejected.method.as_deref(), Some("add"));
+        assert!(rx.try_recv().is_err());
+
+        // The genuine answer still gets through.
+        table
+            .resolve(answer(&request, json!(3)), Some("main"))
+            .unwrap();
+        assert!(settled(&mut rx).is_ok());
+    }
+
+    #[test]
+    fn resolve_rejects_the_wrong_origin() {
+        let table = PendingTable::new();
+        let (request, mut rx) = table.request("add", None, Some("main".into())).unwrap();
+        let reason = rejection(&table, answer(&request, json!(3)), Some("settings"));
+        assert_eq!(reason, RejectReason::WrongOrigin);
+        assert_eq!(table.len(), 1);
+
+        // Transports that can't tell the sender rely on the token alone.
+        table.resolve(answer(&request, json!(3)), None).unwrap();
+        assert!(settled(&mut rx).is_ok());
+    }
+
+    #[test]
+    fn resolve_reports_duplicates_and_unknown_ids() {
+        let table = PendingTable::new();
+        let (request, _rx) = table.request("add", None, Some("main".into())).unwrap();
+        table
+            .resolve(answer(&request, json!(3)), Some("main"))
+            .unwrap();
+        let reason = rejection(&table, answer(&request, json!(3)), Some("main"));
+        assert_eq!(reason, RejectReason::AlreadyResolved);
+
+        let unknown = FrontInvokeResponse {
+            id: request.id + 100,
+            ..answer(&request, json!(3))
+        };
+        assert_eq!(rejection(&table, unknown, None), RejectReason::UnknownId);
+    }
+
+    #[test]
+    fn resolve_accepts_late_answers_to_a_broadcast() {
+        let table = PendingTable::new();
+        let (request, mut rx) = table.request("ping", None, None).unwrap();
+        table
+            .resolve(answer(&request, json!("a")), Some("a"))
+            .unwrap();
+        table
+            .resolve(answer(&request, json!("b")), Some("b"))
+            .unwrap();
+        let first: String = settled(&mut rx).unwrap().into_result("ping").unwrap();
+        assert_eq!(first, "a");
+    }
+
+    #[test]
+    fn resolve_rejects_forged_late_answers_to_a_broadcast() {
+        let table = PendingTable::new();
+        let (request, _rx) = table.request("ping", None, None).unwrap();
+        table
+            .resolve(answer(&request, json!("a")), Some("a"))
+            .unwrap();
+        let forged = FrontInvokeResponse {
+            token: Some("forged".into()),
+            ..answer(&request, json!("b"))
+        };
+        let rejected = table.resolve(forged, Some("b")).unwrap_err();
+        assert_eq!(rejected.reason, RejectReason::TokenMismatch);
+        assert_eq!(rejected.method.as_deref(), Some("ping"));
+    }
+
+    #[test]
+    fn resolve_fails_a_stream_that_exceeds_its_credit() {
+        let table = PendingTable::new();
+        let (request, mut chunks, mut rx) = table.request_stream("lines", None, None, 1).unwrap();
+        assert_eq!(request.credit, Some(1));
+        let chunk = |data| FrontInvokeResponse {
+            chunk: true,
+            ..answer(&request, data)
+        };
+        table.resolve(chunk(json!("a")), None).unwrap();
+        assert_eq!(
+            rejection(&table, chunk(json!("b")), None),
+            RejectReason::StreamFull
+        );
+
+        assert!(table.is_empty());
+        assert_eq!(chunks.try_recv().unwrap(), json!("a"));
+        let err = settled(&mut rx).unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        let overflow = source.downcast_ref::<StreamOverflow>().unwrap();
+        assert_eq!(overflow.capacity, 1);
+    }
+
+    #[test]
+    fn resolve_rejects_chunks_for_plain_requests() {
+        let table = PendingTable::new();
+        let (request, _rx) = table.request("add", None, None).unwrap();
+        let chunk = FrontInvokeResponse {
+            chunk: true,
+            ..answer(&request, json!(1))
+        };
+        assert_eq!(rejection(&table, chunk, None), RejectReason::NotStreaming);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn resolve_routes_progress_to_the_callback() {
+        let table = PendingTable::new();
+        let (request, mut rx) = table.request("export", None, None).unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let callback: ProgressCallback = {
+            let seen = Arc::clone(&seen);
+            Arc::new(move |update| seen.lock().unwrap().push(update))
+        };
+        assert!(table.on_progress(request.id, callback));
+
+        let progress = FrontInvokeResponse {
+            progress: true,
+            ..answer(&request, json!(50))
+        };
+        table.resolve(progress, None).unwrap();
+        assert_eq!(*seen.lock().unwrap(), [json!(50)]);
+        assert!(rx.try_recv().is_err());
+
+        table
+            .resolve(answer(&request, json!("done")), None)
+            .unwrap();
+        assert!(settled(&mut rx).is_ok());
+        assert!(!table.on_progress(request.id, Arc::new(|_| {})));
+    }
+
+    #[test]
+    fn fail_label_settles_only_that_webview() {
+        let table = PendingTable::new();
+        let (_, mut gone) = table.request("a", None, Some("main".into())).unwrap();
+        let (kept, _kept_rx) = table.request("b", None, Some("main".into())).unwrap();
+        let (_, mut other) = table.request("c", None, Some("settings".into())).unwrap();
+
+        let failed = table.fail_label("main", FrontendGoneReason::Navigated, &[kept.id]);
+        assert_eq!(failed, 1);
+        assert_eq!(table.len(), 2);
+        assert!(other.try_recv().is_err());
+
+        let err = settled(&mut gone).unwrap_err();
+        assert!(err.is_retryable());
+        let source = std::error::Error::source(&err).unwrap();
+        let gone = source.downcast_ref::<FrontendGone>().unwrap();
+        assert_eq!((gone.method.as_str(), gone.label.as_str()), ("a", "main"));
+    }
+
+    #[test]
+    fn bare_string_errors_decode_as_the_message() {
+        let resp: FrontInvokeResponse =
+            serde_json::from_value(json!({ "id": 1, "success": false, "error": "boom" })).unwrap();
+        assert_eq!(resp.error, Some(ErrorPayload::new("boom")));
+
+        let resp: FrontInvokeResponse = serde_json::from_value(json!({
+            "id": 1,
+            "success": false,
+            "error": { "code": "denied", "message": "no" },
+        }))
+        .unwrap();
+        assert_eq!(resp.error, Some(ErrorPayload::new("no").code("denied")));
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(tag = "code", content = "details", rename_all = "camelCase")]
+    enum PickError {
+        UserCancelled,
+        Denied { reason: String },
+    }
+
+    fn pick_failed(error: ErrorPayload) -> FrontError<PickError> {
+        FrontError::from(FrontInvokeError::Frontend {
+            method: "pickFile".into(),
+            error: Box::new(error),
+        })
+    }
+
+    #[test]
+    fn front_error_decodes_a_code() {
+        let err = pick_failed(ErrorPayload::new("cancelled").code("userCancelled"));
+        assert!(
+            matches!(err, FrontError::App(PickError::UserCancelled)),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn front_error_decodes_details() {
+        let error = ErrorPayload::new("not allowed")
+            .code("denied")
+            .details(json!({ "reason": "policy" }));
+        let FrontError::App(app) = pick_failed(error) else {
+            panic!("expected an app error");
+        };
+        assert_eq!(
+            app,
+            PickError::Denied {
+                reason: "policy".into()
+            }
+        );
+    }
+
+    #[test]
+    fn front_error_keeps_everything_else_as_invoke() {
+        let err = pick_failed(ErrorPayload::new("disk full").code("ioError"));
+        let FrontError::Invoke(err) = err else {
+            panic!("expected an invoke error");
+        };
+        assert_eq!(err.payload().map(|p| p.message.as_str()), Some("disk full"));
+
+        assert!(matches!(
+            pick_failed(ErrorPayload::new("no code")),
+            FrontError::Invoke(FrontInvokeError::Frontend { .. })
+        ));
+
+        let timeout = FrontInvokeTimeout {
+            method: "pickFile".into(),
+            timeout: Duration::from_secs(1),
+        };
+        assert!(matches!(
+            FrontError::<PickError>::from(FrontInvokeError::from(timeout)),
+            FrontError::Invoke(FrontInvokeError::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn handler_registry_answers_calls() {
+        let registry = HandlerRegistry::new();
+        registry.handle("add", async |(a, b): (u32, u32)| Ok(a + b));
+        registry.handle("deny", async |()| -> Result<()> {
+            Err(ErrorPayload::new("denied").code("forbidden").into())
+        });
+        let call = |method: &str, payload| FrontCall {
+            id: 7,
+            method: method.into(),
+            payload,
+        };
+
+        let reply = registry.call(call("add", Some(json!([1, 2])))).await;
+        assert_eq!((reply.id, reply.success), (7, true));
+        assert_eq!(reply.data, Some(json!(3)));
+
+        let reply = registry.call(call("add", Some(json!("x")))).await;
+        assert!(!reply.success);
+        assert!(
+            reply
+                .error
+                .unwrap()
+                .message
+                .contains("deserialize call payload")
+        );
+
+        let reply = registry.call(call("deny", None)).await;
+        assert_eq!(
+            reply.error,
+            Some(ErrorPayload::new("denied").code("forbidden"))
+        );
+
+        let reply = registry.call(call("missing", None)).await;
+        assert_eq!(
+            reply.error,
+            Some(ErrorPayload::new("no handler for missing"))
+        );
+    }
+}
@@ -0,0 +1,680 @@
+//! An in-process stand-in for the webview, for exercising bridge callers without a UI.
+//!
+//! ```ignore
+//! let app = tauri::test::mock_app();
+//! let fake = FakeFrontend::new();
+//! let bridge = fake.install(app.handle());
+//! fake.on("confirm").respond(true);
+//! fake.on("pickFile").delay(Duration::from_millis(50)).fail("cancelled");
+//!
+//! run_code_under_test(app.handle()).await;
+//! fake.assert_call_order(&["confirm", "pickFile"]);
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+
+use crate::{
+    BridgeConfig, Destination, ErrorPayload, FrontBridge, FrontInvokeCancel, FrontInvokeCredit,
+    FrontInvokeRequest, FrontInvokeResponse, Inbound, Transport,
+};
+
+/// One request the fake frontend received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    /// The request id; `0` for notifications.
+    pub id: u64,
+    pub method: String,
+    /// The addressed webview; `None` for broadcasts.
+    pub label: Option<String>,
+    /// `Value::Null` when the request had no payload.
+    pub payload: Value,
+}
+
+#[derive(Clone)]
+enum Reply {
+    Data(Value),
+    Error(ErrorPayload),
+    Stream(Vec<Value>),
+    Never,
+}
+
+#[derive(Clone)]
+struct Script {
+    delay: Option<Duration>,
+    progress: Vec<Value>,
+    reply: Reply,
+}
+
+/// Scripts keyed by method and, for scripts that only apply to one webview, its label.
+type ScriptKey = (String, Option<String>);
+
+#[derive(Default)]
+struct Shared {
+    scripts: Mutex<HashMap<ScriptKey, Script>>,
+    calls: Mutex<Vec<RecordedCall>>,
+    /// Ids the bridge cancelled, in order.
+    cancels: Mutex<Vec<u64>>,
+    /// Where credit for each stream the fake is still sending goes.
+    credits: Mutex<HashMap<u64, mpsc::UnboundedSender<u32>>>,
+    inbound: Mutex<Option<Inbound>>,
+}
+
+impl Shared {
+    fn is_cancelled(&self, id: u64) -> bool {
+        self.cancels
+            .lock()
+            .expect("fake frontend cancel log poisoned")
+            .contains(&id)
+    }
+}
+
+/// Answers the bridge's requests from scripted per-method replies and records every call.
+///
+/// It is the bridge's `Transport`, so it sees exactly what a real frontend would: install it
+/// with `install`, or pass a clone to `Builder::transport`. Methods without a script fail
+/// with "no fake handler"; `MethodStub::webview` scripts one webview differently from the rest.
+#[derive(Clone, Default)]
+pub struct FakeFrontend {
+    shared: Arc<Shared>,
+}
+
+impl FakeFrontend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a bridge on `app_handle` that talks to this fake and manages it, so the free
+    /// `invoke_frontend*` functions use it too.
+    ///
+    /// Panics if the app already manages a bridge; install the fake first, or use
+    /// `Builder::transport` when the plugin is registered.
+    pub fn install<R: Runtime>(&self, app_handle: &AppHandle<R>) -> FrontBridge<R> {
+        assert!(
+            app_handle.try_state::<FrontBridge<R>>().is_none(),
+            "a FrontBridge is already managed; install the fake frontend before anything uses it"
+        );
+        let config = BridgeConfig {
+            transport: Some(Arc::new(self.clone())),
+            ..BridgeConfig::default()
+        };
+        let bridge = FrontBridge::with_config(app_handle, config);
+        app_handle.manage(bridge.clone());
+        bridge
+    }
+
+    /// Scripts the reply to `method`, replacing any earlier script for it.
+    pub fn on(&self, method: impl Into<String>) -> MethodStub<'_> {
+        MethodStub {
+            fake: self,
+            method: method.into(),
+            label: None,
+            delay: None,
+            progress: Vec::new(),
+        }
+    }
+
+    /// Announces `label` as ready, flushing requests queued for it.
+    pub fn ready(&self, label: &str) {
+        if let Some(inbound) = self.inbound() {
+            inbound.ready(label);
+        }
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.shared
+            .calls
+            .lock()
+            .expect("fake frontend call log poisoned")
+            .clone()
+    }
+
+    /// Payloads of every call to `method`, oldest first.
+    pub fn calls_to(&self, method: &str) -> Vec<Value> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.method == method)
+            .map(|call| call.payload)
+            .collect()
+    }
+
+    #[track_caller]
+    pub fn assert_called(&self, method: &str) {
+        assert!(
+            !self.calls_to(method).is_empty(),
+            "expected {method} to be called; calls were {:?}",
+            self.methods()
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_not_called(&self, method: &str) {
+        assert!(
+            self.calls_to(method).is_empty(),
+            "expected {method} not to be called; calls were {:?}",
+            self.methods()
+        );
+    }
+
+    /// Asserts that at least one call to `method` carried exactly `payload`.
+    #[track_caller]
+    pub fn assert_called_with(&self, method: &str, payload: impl Serialize) {
+        let expected = serde_json::to_value(payload).expect("serialize expected payload");
+        let payloads = self.calls_to(method);
+        assert!(
+            payloads.contains(&expected),
+            "expected {method} to be called with {expected}; got {payloads:?}"
+        );
+    }
+
+    /// Asserts the exact sequence of methods called so far.
+    #[track_caller]
+    pub fn assert_call_order(&self, methods: &[&str]) {
+        assert_eq!(self.methods(), methods, "unexpected frontend call order");
+    }
+
+    /// Calls the bridge cancelled, in the order the cancels arrived.
+    pub fn cancelled(&self) -> Vec<RecordedCall> {
+        let calls = self.calls();
+        self.shared
+            .cancels
+            .lock()
+            .expect("fake frontend cancel log poisoned")
+            .iter()
+            .filter_map(|id| calls.iter().find(|call| call.id == *id).cloned())
+            .collect()
+    }
+
+    #[track_caller]
+    pub fn assert_cancelled(&self, method: &str) {
+        let cancelled = self.cancelled();
+        assert!(
+            cancelled.iter().any(|call| call.method == method),
+            "expected a call to {method} to be cancelled; cancelled were {:?}",
+            cancelled
+                .iter()
+                .map(|call| &call.method)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_not_cancelled(&self, method: &str) {
+        let cancelled = self.cancelled();
+        assert!(
+            !cancelled.iter().any(|call| call.method == method),
+            "expected no call to {method} to be cancelled; cancelled were {:?}",
+            cancelled
+                .iter()
+                .map(|call| &call.method)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Forgets the calls and cancels recorded so far.
+    pub fn clear_calls(&self) {
+        self.shared
+            .calls
+            .lock()
+            .expect("fake frontend call log poisoned")
+            .clear();
+        self.shared
+            .cancels
+            .lock()
+            .expect("fake frontend cancel log poisoned")
+            .clear();
+    }
+
+    fn methods(&self) -> Vec<String> {
+        self.calls().into_iter().map(|call| call.method).collect()
+    }
+
+    fn script(&self, key: ScriptKey, script: Script) {
+        self.shared
+            .scripts
+            .lock()
+            .expect("fake frontend scripts poisoned")
+            .insert(key, script);
+    }
+
+    fn inbound(&self) -> Option<Inbound> {
+        self.shared
+            .inbound
+            .lock()
+            .expect("fake frontend inbound poisoned")
+            .clone()
+    }
+}
+
+impl Transport for FakeFrontend {
+    fn start(&self, inbound: Inbound) -> Result<()> {
+        *self
+            .shared
+            .inbound
+            .lock()
+            .expect("fake frontend inbound poisoned") = Some(inbound);
+        Ok(())
+    }
+
+    fn send_request(&self, destination: &Destination, request: &FrontInvokeRequest) -> Result<()> {
+        if let Some(inbound) = self.inbound() {
+            answer(&inbound, &self.shared, destination, request.clone());
+        }
+        Ok(())
+    }
+
+    /// Records the cancel; a delayed reply to the call is then never sent.
+    fn send_cancel(&self, _destination: &Destination, cancel: &FrontInvokeCancel) -> Result<()> {
+        self.shared
+            .cancels
+            .lock()
+            .expect("fake frontend cancel log poisoned")
+            .push(cancel.id);
+        self.shared
+            .credits
+            .lock()
+            .expect("fake frontend credits poisoned")
+            .remove(&cancel.id);
+        Ok(())
+    }
+
+    fn send_credit(&self, _destination: &Destination, credit: &FrontInvokeCredit) -> Result<()> {
+        let credits = self
+            .shared
+            .credits
+            .lock()
+            .expect("fake frontend credits poisoned");
+        if let Some(grants) = credits.get(&credit.id) {
+            let _ = grants.send(credit.credit);
+        }
+        Ok(())
+    }
+
+    fn stop(&self) {
+        self.shared
+            .inbound
+            .lock()
+            .expect("fake frontend inbound poisoned")
+            .take();
+    }
+}
+
+/// Pending script for one method; finish it with `respond`, `fail`, `stream` or `never_answer`.
+pub struct MethodStub<'a> {
+    fake: &'a FakeFrontend,
+    method: String,
+    label: Option<String>,
+    delay: Option<Duration>,
+    progress: Vec<Value>,
+}
+
+impl MethodStub<'_> {
+    /// Scripts only the webview labelled `label`; other webviews keep the method's unlabelled
+    /// script.
+    pub fn webview(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Holds the reply back for `delay` after the request arrives.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sends `update` as a progress message before the reply; repeat for several updates.
+    pub fn progress(mut self, update: impl Serialize) -> Self {
+        let update = serde_json::to_value(update).expect("serialize fake frontend progress");
+        self.progress.push(update);
+        self
+    }
+
+    pub fn respond(self, data: impl Serialize) {
+        let data = serde_json::to_value(data).expect("serialize fake frontend reply");
+        self.finish(Reply::Data(data));
+    }
+
+    pub fn fail(self, error: impl Into<String>) {
+        self.finish(Reply::Error(ErrorPayload::new(error)));
+    }
+
+    /// Fails with a structured error, e.g. `ErrorPayload::new("denied").code("userCancelled")`.
+    pub fn fail_with(self, error: ErrorPayload) {
+        self.finish(Reply::Error(error));
+    }
+
+    /// Answers with each of `chunks` as a stream chunk, then ends the stream. Like a real
+    /// frontend it sends no more chunks than it has credit for, waiting for the bridge to
+    /// grant more as the stream is consumed.
+    pub fn stream<T: Serialize>(self, chunks: impl IntoIterator<Item = T>) {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| serde_json::to_value(chunk).expect("serialize fake frontend chunk"))
+            .collect();
+        self.finish(Reply::Stream(chunks));
+    }
+
+    /// Records the call but never replies, for exercising timeouts and cancellation.
+    pub fn never_answer(self) {
+        self.finish(Reply::Never);
+    }
+
+    fn finish(self, reply: Reply) {
+        let script = Script {
+            delay: self.delay,
+            progress: self.progress,
+            reply,
+        };
+        self.fake.script((self.method, self.label), script);
+    }
+}
+
+fn answer(
+    inbound: &Inbound,
+    shared: &Arc<Shared>,
+    destination: &Destination,
+    request: FrontInvokeRequest,
+) {
+    shared
+        .calls
+        .lock()
+        .expect("fake frontend call log poisoned")
+        .push(RecordedCall {
+            id: request.id,
+            method: request.method.clone(),
+            label: destination.label().map(str::to_string),
+            payload: request.payload.clone().unwrap_or(Value::Null),
+        });
+    if request.notify {
+        return;
+    }
+    let scripts = shared
+        .scripts
+        .lock()
+        .expect("fake frontend scripts poisoned");
+    let labelled = (
+        request.method.clone(),
+        destination.label().map(str::to_string),
+    );
+    let script = scripts
+        .get(&labelled)
+        .or_else(|| scripts.get(&(request.method.clone(), None)))
+        .cloned()
+        .unwrap_or_else(|| Script {
+            delay: None,
+            progress: Vec::new(),
+            reply: Reply::Error(ErrorPayload::new(format!(
+                "no fake handler for {}",
+                request.method
+            ))),
+        });
+    drop(scripts);
+    let respond = |success, data, error, chunk| FrontInvokeResponse {
+        id: request.id,
+        token: Some(request.token.clone()),
+        success,
+        data,
+        error,
+        chunk,
+        progress: false,
+    };
+    let mut replies: Vec<_> = script
+        .progress
+        .into_iter()
+        .map(|update| FrontInvokeResponse {
+            progress: true,
+            ..respond(true, Some(update), None, false)
+        })
+        .collect();
+    match script.reply {
+        Reply::Data(data) => replies.push(respond(true, Some(data), None, false)),
+        Reply::Error(error) => replies.push(respond(false, None, Some(error), false)),
+        Reply::Stream(chunks) => replies.extend(
+            chunks
+                .into_iter()
+                .map(|chunk| respond(true, Some(chunk), None, true))
+                .chain([respond(true, None, None, false)]),
+        ),
+        Reply::Never => {}
+    }
+    if replies.is_empty() {
+        return;
+    }
+    // Answer as the addressed webview, so origin checks apply as they would for a real one.
+    let origin = destination.label().map(str::to_string);
+    let deliver = move |inbound: &Inbound, reply| match &origin {
+        Some(origin) => {
+            let _ = inbound.response_from(origin, reply);
+        }
+        None => inbound.response(reply),
+    };
+    let mut credit = request.credit.map_or(usize::MAX, |credit| credit as usize);
+    let chunks = replies.iter().filter(|reply| reply.chunk).count();
+    // Undelayed replies arrive while the bridge is still sending, as with a transport that
+    // answers on the same thread.
+    if script.delay.is_none() && chunks <= credit {
+        for reply in replies {
+            deliver(inbound, reply);
+        }
+        return;
+    }
+    let id = request.id;
+    let mut grants = request.stream.then(|| {
+        let (grant, grants) = mpsc::unbounded_channel();
+        shared
+            .credits
+            .lock()
+            .expect("fake frontend credits poisoned")
+            .insert(id, grant);
+        grants
+    });
+    let (inbound, shared) = (inbound.clone(), Arc::clone(shared));
+    tauri::async_runtime::spawn(async move {
+        if let Some(delay) = script.delay {
+            tokio::time::sleep(delay).await;
+        }
+        for reply in replies {
+            if reply.chunk {
+                while credit == 0 {
+                    let more = match &mut grants {
+                        Some(grants) => grants.recv().await,
+                        None => None,
+                    };
+                    // Cancelling the stream drops the sender.
+                    let Some(more) = more else {
+                        return;
+                    };
+                    credit += more as usize;
+                }
+                credit -= 1;
+            }
+            // A real frontend stops working on a cancelled call.
+            if shared.is_cancelled(id) {
+                return;
+            }
+            deliver(&inbound, reply);
+        }
+        shared
+            .credits
+            .lock()
+            .expect("fake frontend credits poisoned")
+            .remove(&id);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use futures_util::StreamExt;
+    use tauri::test::mock_app;
+
+    use super::FakeFrontend;
+    use crate::{Builder, FrontInvokeError, FrontMethod, InvokeOptions, InvokeTarget};
+
+    struct Confirm;
+
+    impl FrontMethod for Confirm {
+        const NAME: &'static str = "confirm";
+        type Params = String;
+        type Output = bool;
+    }
+
+    #[test]
+    fn scripted_round_trip() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("confirm").respond(true);
+        fake.on("pickFile")
+            .delay(Duration::from_millis(20))
+            .fail("cancelled");
+        fake.on("hang").never_answer();
+
+        tauri::async_runtime::block_on(async {
+            let confirmed = bridge.invoke::<Confirm>("Delete?".into()).await.unwrap();
+            assert!(confirmed);
+
+            let err = bridge
+                .invoke_by_name::<String, _>("pickFile", ())
+                .await
+                .unwrap_err();
+            assert_eq!(err.payload().map(|p| p.message.as_str()), Some("cancelled"));
+
+            let options = InvokeOptions::new().timeout(Duration::from_millis(20));
+            let err = bridge
+                .invoke_by_name_with::<(), _>("hang", (), options)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, FrontInvokeError::Timeout(_)), "{err:?}");
+
+            let err = bridge
+                .invoke_by_name::<(), _>("unscripted", ())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("no fake handler"), "{err}");
+        });
+
+        fake.assert_call_order(&["confirm", "pickFile", "hang", "unscripted"]);
+        fake.assert_cancelled("hang");
+        fake.assert_not_cancelled("pickFile");
+        fake.assert_called_with("confirm", "Delete?");
+        fake.assert_not_called("save");
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn progress_and_stream() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("export").progress(50).progress(100).respond("done");
+        fake.on("lines").stream(["a", "b"]);
+
+        tauri::async_runtime::block_on(async {
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let options = InvokeOptions::new().on_progress({
+                let seen = Arc::clone(&seen);
+                move |percent: u32| seen.lock().unwrap().push(percent)
+            });
+            let result: String = bridge
+                .invoke_by_name_with("export", (), options)
+                .await
+                .unwrap();
+            assert_eq!(result, "done");
+            assert_eq!(*seen.lock().unwrap(), [50, 100]);
+
+            let lines: Vec<String> = bridge
+                .invoke_stream("lines", ())
+                .map(Result::unwrap)
+                .collect()
+                .await;
+            assert_eq!(lines, ["a", "b"]);
+        });
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn streams_longer_than_the_buffer_wait_for_credit() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let bridge = Builder::new()
+            .stream_capacity(4)
+            .security_hook({
+                let rejected = Arc::clone(&rejected);
+                move |response| rejected.lock().unwrap().push(response.reason)
+            })
+            .transport(fake.clone())
+            .build_bridge(app.handle());
+        fake.on("lines").stream(0..20);
+
+        let lines: Vec<u32> = tauri::async_runtime::block_on(
+            bridge
+                .invoke_stream::<u32, _, _>("lines", ())
+                .map(Result::unwrap)
+                .collect(),
+        );
+
+        assert_eq!(lines, (0..20).collect::<Vec<_>>());
+        assert!(rejected.lock().unwrap().is_empty());
+        fake.assert_not_cancelled("lines");
+        assert_eq!(bridge.pending_count(), 0);
+    }
+
+    #[test]
+    fn requests_go_out_without_ready_by_default() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = fake.install(app.handle());
+        fake.on("ping").respond("pong");
+
+        let options = InvokeOptions::new().target(InvokeTarget::webview("child"));
+        let pong = tauri::async_runtime::block_on(bridge.invoke_by_name_with::<String, _>(
+            "ping",
+            (),
+            options,
+        ));
+        assert_eq!(pong.unwrap(), "pong");
+        assert!(!bridge.frontend_ready("child"));
+    }
+
+    #[test]
+    fn requests_wait_for_ready() {
+        let app = mock_app();
+        let fake = FakeFrontend::new();
+        let bridge = Builder::new()
+            .wait_for_ready(true)
+            .transport(fake.clone())
+            .build_bridge(app.handle());
+        fake.on("ping").respond("pong");
+
+        tauri::async_runtime::block_on(async {
+            let options = InvokeOptions::new().target(InvokeTarget::Webview("child".into()));
+            let call = tauri::async_runtime::spawn({
+                let bridge = bridge.clone();
+                async move {
+                    bridge
+                        .invoke_by_name_with::<String, _>("ping", (), options)
+                        .await
+                }
+            });
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            fake.assert_not_called("ping");
+
+            fake.ready("child");
+            assert_eq!(call.await.unwrap().unwrap(), "pong");
+        });
+        assert!(bridge.frontend_ready("child"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use anyhow::{Context, Result, anyhow};
+use tauri::{AppHandle, Emitter, EventId, Listener, Runtime};
+
+use crate::{
+    BridgeConfig, CANCEL_EVENT, CREDIT_EVENT, Destination, FrontCall, FrontInvokeCancel,
+    FrontInvokeCredit, FrontInvokeRequest, FrontInvokeResponse, FrontInvokeState, FrontReady,
+    FrontendGoneReason, READY_EVENT, REPLY_EVENT, REQUEST_EVENT, RESPONSE_EVENT, RejectReason,
+};
+
+/// Moves protocol messages between the bridge and a frontend.
+///
+/// The bridge owns ids, tokens, readiness and the pending table; a transport only delivers
+/// requests, cancellations, stream credits and call replies outward and feeds whatever the frontend sends
+/// into `Inbound`.
+pub trait Transport: Send + Sync + 'static {
+    /// Called once when the bridge is created.
+    fn start(&self, inbound: Inbound) -> Result<()>;
+
+    fn send_request(&self, destination: &Destination, request: &FrontInvokeRequest) -> Result<()>;
+
+    fn send_cancel(&self, destination: &Destination, cancel: &FrontInvokeCancel) -> Result<()>;
+
+    /// Lets a stream's frontend send more chunks. A stream whose credits can't be delivered
+    /// stalls once the frontend has used up the request's initial `credit`.
+    fn send_credit(&self, destination: &Destination, credit: &FrontInvokeCredit) -> Result<()>;
+
+    /// Answers a frontend-initiated `FrontCall`. Transports that never feed calls into
+    /// `Inbound` can leave this unimplemented.
+    fn send_reply(&self, _destination: &Destination, reply: &FrontInvokeResponse) -> Result<()> {
+        Err(anyhow!(
+            "transport can't deliver the reply to call id={}",
+            reply.id
+        ))
+    }
+
+    /// The webview `label` was destroyed, navigated away or replaced. Forget anything held for
+    /// it; the next page under the label connects or announces itself afresh.
+    fn frontend_gone(&self, _label: &str) {}
+
+    /// Called when the bridge shuts down; stop delivering to `Inbound`.
+    fn stop(&self) {}
+}
+
+/// The bridge's receiving end, handed to `Transport::start`.
+#[derive(Clone)]
+pub struct Inbound {
+    state: Weak<FrontInvokeState>,
+}
+
+impl Inbound {
+    pub(crate) fn new(state: &Arc<FrontInvokeState>) -> Self {
+        Self {
+            state: Arc::downgrade(state),
+        }
+    }
+
+    /// Settles the request `resp` answers. Rejections are reported to the security hook.
+    pub fn response(&self, resp: FrontInvokeResponse) {
+        if let Some(state) = self.state.upgrade() {
+            let _ = state.resolve(resp, None);
+        }
+    }
+
+    /// Like `response`, for transports that know which webview sent it; a response from
+    /// any other webview than the request's destination is rejected.
+    pub fn response_from(
+        &self,
+        origin: &str,
+        resp: FrontInvokeResponse,
+    ) -> std::result::Result<(), RejectReason> {
+        match self.state.upgrade() {
+            Some(state) => state.resolve(resp, Some(origin)),
+            None => Err(RejectReason::UnknownId),
+        }
+    }
+
+    /// Serves a call the frontend made into a handler registered on the bridge. Only feed
+    /// calls whose sender the transport has authenticated as `origin`; the reply goes out to
+    /// it via `send_reply`.
+    pub fn call(&self, origin: &str, call: FrontCall) {
+        if let Some(state) = self.state.upgrade() {
+            state.serve_call(origin.to_string(), call);
+        }
+    }
+
+    /// The frontend behind `label` went away without closing its window, e.g. a dropped
+    /// connection. Requests it was handling fail; new ones queue until it is ready again.
+    pub fn disconnected(&self, label: &str) {
+        if let Some(state) = self.state.upgrade() {
+            state.frontend_gone(label, FrontendGoneReason::Navigated);
+        }
+    }
+
+    /// Marks the webview `label` as ready, flushing requests queued for it.
+    pub fn ready(&self, label: &str) {
+        if let Some(state) = self.state.upgrade() {
+            state.mark_ready(label);
+        }
+    }
+}
+
+/// The default transport: requests, cancellations, credits and call replies are emitted as Tauri
+/// events to the destination's `EventTarget`; responses and readiness arrive as global events.
+///
+/// Global events don't say which webview emitted them, so frontend calls don't come in
+/// this way; webviews make them through the plugin's `call` command.
+pub struct EventTransport<R: Runtime> {
+    app_handle: AppHandle<R>,
+    request_event: String,
+    response_event: String,
+    cancel_event: String,
+    credit_event: String,
+    ready_event: String,
+    reply_event: String,
+    listeners: Mutex<Vec<EventId>>,
+}
+
+impl<R: Runtime> EventTransport<R> {
+    pub fn new(app_handle: &AppHandle<R>) -> Self {
+        Self {
+            app_handle: app_handle.clone(),
+            request_event: REQUEST_EVENT.to_string(),
+            response_event: RESPONSE_EVENT.to_string(),
+            cancel_event: CANCEL_EVENT.to_string(),
+            credit_event: CREDIT_EVENT.to_string(),
+            ready_event: READY_EVENT.to_string(),
+            reply_event: REPLY_EVENT.to_string(),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn with_config(app_handle: &AppHandle<R>, config: &BridgeConfig) -> Self {
+        Self {
+            request_event: config.request_event.clone(),
+            response_event: config.response_event.clone(),
+            cancel_event: config.cancel_event.clone(),
+            credit_event: config.credit_event.clone(),
+            ready_event: config.ready_event.clone(),
+            reply_event: config.reply_event.clone(),
+            ..Self::new(app_handle)
+        }
+    }
+
+    fn emit<S: serde::Serialize>(
+        &self,
+        destination: &Destination,
+        event: &str,
+        payload: &S,
+    ) -> Result<()> {
+        self.app_handle
+            .emit_to(destination.event_target().clone(), event, payload)
+            .with_context(|| match destination.label() {
+                Some(label) => format!("emit {event} ({label})"),
+                None => format!("emit {event}"),
+            })
+    }
+}
+
+impl<R: Runtime> Transport for EventTransport<R> {
+    fn start(&self, inbound: Inbound) -> Result<()> {
+        let responses = inbound.clone();
+        let response =
+            self.app_handle.listen_any(
+                &self.response_event,
+                move |event| match serde_json::from_str::<FrontInvokeResponse>(event.payload()) {
+                    Ok(resp) => responses.response(resp),
+                    Err(err) => {
+                        log::error!("[frontbridge] failed to parse response payload: {err}");
+                    }
+                },
+            );
+
+        let ready =
+            self.app_handle.listen_any(
+                &self.ready_event,
+                move |event| match serde_json::from_str::<FrontReady>(event.payload()) {
+                    Ok(ready) => inbound.ready(&ready.label),
+                    Err(err) => {
+                        log::error!("[frontbridge] failed to parse ready payload: {err}");
+                    }
+                },
+            );
+
+        self.listeners
+            .lock()
+            .expect("frontbridge listener list poisoned")
+            .extend([response, ready]);
+        Ok(())
+    }
+
+    fn send_request(&self, destination: &Destination, request: &FrontInvokeRequest) -> Result<()> {
+        self.emit(destination, &self.request_event, request)
+    }
+
+    fn send_cancel(&self, destination: &Destination, cancel: &FrontInvokeCancel) -> Result<()> {
+        self.emit(destination, &self.cancel_event, cancel)
+    }
+
+    fn send_credit(&self, destination: &Destination, credit: &FrontInvokeCredit) -> Result<()> {
+        self.emit(destination, &self.credit_event, credit)
+    }
+
+    fn send_reply(&self, destination: &Destination, reply: &FrontInvokeResponse) -> Result<()> {
+        self.emit(destination, &self.reply_event, reply)
+    }
+
+    fn stop(&self) {
+        let listeners = std::mem::take(
+            &mut *self
+                .listeners
+                .lock()
+                .expect("frontbridge listener list poisoned"),
+        );
+        for id in listeners {
+            self.app_handle.unlisten(id);
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr, TcpListener as StdTcpListener},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    CancellationToken, ChannelMessage, Destination, FrontCall, FrontInvokeCancel,
+    FrontInvokeCredit, FrontInvokeRequest, FrontInvokeResponse, Inbound, Transport, request_token,
+};
+
+/// How long a fresh connection has to send its `hello` before it is dropped, by default.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a browser client sends. The first message must be `hello`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum ClientMessage {
+    Hello { token: String, label: String },
+    Response(FrontInvokeResponse),
+    Call(FrontCall),
+}
+
+struct WebSocketInner {
+    token: String,
+    listener: Mutex<Option<StdTcpListener>>,
+    clients: Mutex<HashMap<String, mpsc::UnboundedSender<String>>>,
+    shutdown: CancellationToken,
+}
+
+/// Serves the bridge protocol over a WebSocket on 127.0.0.1, so a UI running in a normal
+/// browser can answer requests while no Tauri webview is attached.
+///
+/// Clients connect, send `{"kind":"hello","token":…,"label":…}` and then receive the same
+/// `ChannelMessage`s as the channel transport, replying with `{"kind":"response",…}` and
+/// calling backend handlers with `{"kind":"call",…}`.
+/// A successful hello counts as the label's ready announcement.
+#[derive(Clone)]
+pub struct WebSocketTransport {
+    inner: Arc<WebSocketInner>,
+    addr: SocketAddr,
+    hello_timeout: Duration,
+}
+
+impl WebSocketTransport {
+    /// Binds `127.0.0.1:port` (0 picks a free port) with a random token.
+    pub fn bind(port: u16) -> Result<Self> {
+        Self::bind_with_token(port, request_token()?)
+    }
+
+    pub fn bind_with_token(port: u16, token: impl Into<String>) -> Result<Self> {
+        let listener = StdTcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .with_context(|| format!("bind 127.0.0.1:{port}"))?;
+        listener
+            .set_nonblocking(true)
+            .context("make websocket listener non-blocking")?;
+        let addr = listener
+            .local_addr()
+            .context("websocket listener address")?;
+        Ok(Self {
+            inner: Arc::new(WebSocketInner {
+                token: token.into(),
+                listener: Mutex::new(Some(listener)),
+                clients: Mutex::new(HashMap::new()),
+                shutdown: CancellationToken::new(),
+            }),
+            addr,
+            hello_timeout: HELLO_TIMEOUT,
+        })
+    }
+
+    /// How long a fresh connection has to send its `hello` before it is dropped; 10 s unless
+    /// set. Takes effect when the bridge starts the transport.
+    pub fn hello_timeout(mut self, timeout: Duration) -> Self {
+        self.hello_timeout = timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The token clients must present in their `hello`.
+    pub fn token(&self) -> &str {
+        &self.inner.token
+    }
+
+    fn send(&self, destination: &Destination, message: ChannelMessage) -> Result<()> {
+        let text = serde_json::to_string(&message).context("serialize websocket message")?;
+        let clients = self
+            .inner
+            .clients
+            .lock()
+            .expect("frontbridge websocket clients poisoned");
+        let Some(label) = destination.label() else {
+            // Nothing queues a broadcast for a client that says hello later, so reaching no one
+            // must fail the call rather than leave it waiting for its timeout.
+            let delivered = clients
+                .values()
+                .filter(|client| client.send(text.clone()).is_ok())
+                .count();
+            if delivered == 0 {
+                return Err(anyhow!("no websocket client connected"));
+            }
+            return Ok(());
+        };
+        clients
+            .get(label)
+            .ok_or_else(|| anyhow!("no websocket client connected for {label}"))?
+            .send(text)
+            .map_err(|_| anyhow!("websocket client {label} disconnected"))
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn start(&self, inbound: Inbound) -> Result<()> {
+        let listener = self
+            .inner
+            .listener
+            .lock()
+            .expect("frontbridge websocket listener poisoned")
+            .take()
+            .ok_or_else(|| anyhow!("websocket transport already started"))?;
+        let (inner, hello_timeout) = (Arc::clone(&self.inner), self.hello_timeout);
+        log::info!(
+            "[frontbridge] websocket transport listening on {}",
+            self.addr
+        );
+        tauri::async_runtime::spawn(async move {
+            let listener = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(err) => {
+                    log::error!("[frontbridge] websocket listener failed: {err}");
+                    return;
+                }
+            };
+            loop {
+                let stream = tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            log::warn!("[frontbridge] websocket accept failed: {err}");
+                            continue;
+                        }
+                    },
+                    _ = inner.shutdown.cancelled() => break,
+                };
+                let (inner, inbound) = (Arc::clone(&inner), inbound.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = serve(inner, inbound, stream, hello_timeout).await {
+                        log::warn!("[frontbridge] websocket client dropped: {err:#}");
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    fn send_request(&self, destination: &Destination, request: &FrontInvokeRequest) -> Result<()> {
+        self.send(destination, ChannelMessage::Request(request.clone()))
+    }
+
+    fn send_cancel(&self, destination: &Destination, cancel: &FrontInvokeCancel) -> Result<()> {
+        self.send(destination, ChannelMessage::Cancel(cancel.clone()))
+    }
+
+    fn send_credit(&self, destination: &Destination, credit: &FrontInvokeCredit) -> Result<()> {
+        self.send(destination, ChannelMessage::Credit(credit.clone()))
+    }
+
+    fn send_reply(&self, destination: &Destination, reply: &FrontInvokeResponse) -> Result<()> {
+        self.send(destination, ChannelMessage::Reply(reply.clone()))
+    }
+
+    fn stop(&self) {
+        self.inner.shutdown.cancel();
+        self.inner
+            .clients
+            .lock()
+            .expect("frontbridge websocket clients poisoned")
+            .clear();
+    }
+}
+
+async fn serve(
+    inner: Arc<WebSocketInner>,
+    inbound: Inbound,
+    stream: TcpStream,
+    hello_timeout: Duration,
+) -> Result<()> {
+    let mut socket = tokio_tungstenite::accept_async(stream)
+        .await
+        .context("websocket handshake")?;
+    let hello = tokio::time::timeout(hello_timeout, socket.next())
+        .await
+        .map_err(|_| anyhow!("no hello within {hello_timeout:?}"))?
+        .ok_or_else(|| anyhow!("closed before hello"))??;
+    let label = match serde_json::from_str::<ClientMessage>(hello.to_text()?) {
+        Ok(ClientMessage::Hello { token, label }) if tokens_match(&token, &inner.token) => label,
+        Ok(ClientMessage::Hello { .. }) => return Err(anyhow!("bad token")),
+        _ => return Err(anyhow!("expected hello")),
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    inner
+        .clients
+        .lock()
+        .expect("frontbridge websocket clients poisoned")
+        .insert(label.clone(), tx.clone());
+    log::info!("[frontbridge] websocket client {label} connected");
+    inbound.ready(&label);
+
+    let (mut sink, mut source) = socket.split();
+    let result: Result<()> = async {
+        loop {
+            tokio::select! {
+                outgoing = rx.recv() => match outgoing {
+                    Some(text) => sink.send(Message::text(text)).await?,
+                    None => break,
+                },
+                incoming = source.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(ClientMessage::Response(resp)) => {
+                                let _ = inbound.response_from(&label, resp);
+                            }
+                            Ok(ClientMessage::Call(call)) => inbound.call(&label, call),
+                            Ok(ClientMessage::Hello { .. }) => {
+                                log::warn!("[frontbridge] repeated hello from {label}");
+                            }
+                            Err(err) => {
+                                log::error!("[frontbridge] bad websocket message from {label}: {err}");
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Err(err.into()),
+                },
+                _ = inner.shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    let mut clients = inner
+        .clients
+        .lock()
+        .expect("frontbridge websocket clients poisoned");
+    // A reconnect under the same label may already have replaced this client.
+    if clients
+        .get(&label)
+        .is_some_and(|current| current.same_channel(&tx))
+    {
+        clients.remove(&label);
+        drop(clients);
+        inbound.disconnected(&label);
+    }
+    log::info!("[frontbridge] websocket client {label} disconnected");
+    result
+}
+
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{Value, json};
+    use tauri::{
+        App,
+        test::{MockRuntime, mock_app},
+    };
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
+
+    use super::WebSocketTransport;
+    use crate::{
+        Builder, FrontBridge, FrontInvokeError, InvokeOptions, InvokeTarget, RejectReason,
+    };
+
+    type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    fn serve(
+        transport: &WebSocketTransport,
+        rejected: &Arc<Mutex<Vec<RejectReason>>>,
+    ) -> (App<MockRuntime>, FrontBridge<MockRuntime>) {
+        let app = mock_app();
+        let bridge = Builder::new()
+            .transport(transport.clone())
+            .security_hook({
+                let rejected = Arc::clone(rejected);
+                move |response| rejected.lock().unwrap().push(response.reason)
+            })
+            .build_bridge(app.handle());
+        (app, bridge)
+    }
+
+    async fn connect(transport: &WebSocketTransport) -> Client {
+        let url = format!("ws://{}", transport.local_addr());
+        tokio_tungstenite::connect_async(url).await.unwrap().0
+    }
+
+    async fn send(client: &mut Client, message: Value) {
+        client
+            .send(Message::text(message.to_string()))
+            .await
+            .unwrap();
+    }
+
+    async fn hello(transport: &WebSocketTransport, token: &str, label: &str) -> Client {
+        let mut client = connect(transport).await;
+        send(
+            &mut client,
+            json!({ "kind": "hello", "token": token, "label": label }),
+        )
+        .await;
+        client
+    }
+
+    /// Whether the server hangs up on `client` within a second.
+    async fn hung_up(client: &mut Client) -> bool {
+        let next = tokio::time::timeout(Duration::from_secs(1), client.next()).await;
+        matches!(next, Ok(None | Some(Err(_)) | Some(Ok(Message::Close(_)))))
+    }
+
+    async fn until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("condition not met within a second");
+    }
+
+    #[test]
+    fn a_hello_with_the_wrong_token_is_turned_away() {
+        let transport = WebSocketTransport::bind_with_token(0, "secret").unwrap();
+        let rejected = Arc::default();
+        let (_app, bridge) = serve(&transport, &rejected);
+
+        tauri::async_runtime::block_on(async {
+            let mut intruder = hello(&transport, "guess", "main").await;
+            assert!(hung_up(&mut intruder).await);
+            assert!(!bridge.frontend_ready("main"));
+
+            let _client = hello(&transport, "secret", "main").await;
+            until(|| bridge.frontend_ready("main")).await;
+        });
+    }
+
+    #[test]
+    fn a_silent_connection_is_dropped_after_the_hello_timeout() {
+        let transport = WebSocketTransport::bind(0)
+            .unwrap()
+            .hello_timeout(Duration::from_millis(50));
+        let rejected = Arc::default();
+        let (_app, _bridge) = serve(&transport, &rejected);
+
+        tauri::async_runtime::block_on(async {
+            let started = Instant::now();
+            let mut client = connect(&transport).await;
+            assert!(hung_up(&mut client).await);
+            assert!(started.elapsed() >= Duration::from_millis(50));
+        });
+    }
+
+    #[test]
+    fn only_the_addressed_client_can_answer() {
+        let transport = WebSocketTransport::bind(0).unwrap();
+        let rejected = Arc::default();
+        let (_app, bridge) = serve(&transport, &rejected);
+
+        tauri::async_runtime::block_on(async {
+            let mut a = hello(&transport, transport.token(), "a").await;
+            let mut b = hello(&transport, transport.token(), "b").await;
+            until(|| bridge.frontend_ready("a") && bridge.frontend_ready("b")).await;
+
+            let call = tauri::async_runtime::spawn({
+                let bridge = bridge.clone();
+                async move {
+                    let options = InvokeOptions::new().target(InvokeTarget::webview("a"));
+                    bridge
+                        .invoke_by_name_with::<String, _>("ping", (), options)
+                        .await
+                }
+            });
+            let request = a.next().await.unwrap().unwrap();
+            let request: Value = serde_json::from_str(request.to_text().unwrap()).unwrap();
+            assert_eq!(request["kind"], "request");
+            let response = json!({
+                "kind": "response",
+                "id": request["id"],
+                "token": request["token"],
+                "success": true,
+                "data": "pong",
+            });
+
+            // b saw the id and token somehow; answering from the wrong connection still fails.
+            send(&mut b, response.clone()).await;
+            until(|| !rejected.lock().unwrap().is_empty()).await;
+            assert_eq!(*rejected.lock().unwrap(), [RejectReason::WrongOrigin]);
+
+            send(&mut a, response).await;
+            assert_eq!(call.await.unwrap().unwrap(), "pong");
+        });
+    }
+
+    #[test]
+    fn a_broadcast_without_clients_fails() {
+        let transport = WebSocketTransport::bind(0).unwrap();
+        let rejected = Arc::default();
+        let (_app, bridge) = serve(&transport, &rejected);
+
+        let options = InvokeOptions::new().target(InvokeTarget::Any);
+        let err = tauri::async_runtime::block_on(bridge.invoke_by_name_with::<String, _>(
+            "ping",
+            (),
+            options,
+        ))
+        .unwrap_err();
+
+        let FrontInvokeError::Transport { source, .. } = &err else {
+            panic!("expected a transport error, got {err:?}");
+        };
+        assert!(
+            format!("{source:#}").contains("no websocket client connected"),
+            "{source:#}"
+        );
+        assert_eq!(bridge.pending_count(), 0);
+    }
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "test-support")]
+
+use frontbridge::{FrontInvokeError, testing::FakeFrontend};
+use tauri::test::mock_app;
+
+/// Dialogs the main window offers.
+#[frontbridge::interface]
+pub trait MainUi {
+    async fn confirm(&self, title: String) -> bool;
+
+    #[frontbridge(rename = "toast.show")]
+    async fn show_toast(&self, text: String, duration_ms: u64);
+
+    #[deprecated = "use confirm"]
+    async fn ask(&self, question: String) -> bool;
+}
+
+/// Works against any implementation, such as a hand-written fake.
+async fn confirm_delete(ui: &impl MainUi) -> Result<bool, FrontInvokeError> {
+    ui.confirm("Delete?".into()).await
+}
+
+#[test]
+fn client_calls_the_frontend() {
+    let app = mock_app();
+    let fake = FakeFrontend::new();
+    let bridge = fake.install(app.handle());
+    fake.on("confirm").respond(true);
+    fake.on("toast.show").respond(());
+
+    let ui = MainUiClient::from_bridge(bridge);
+    tauri::async_runtime::block_on(async {
+        assert!(confirm_delete(&ui).await.unwrap());
+        ui.show_toast("Deleted".into(), 3000).await.unwrap();
+    });
+
+    fake.assert_call_order(&["confirm", "toast.show"]);
+    fake.assert_called_with("confirm", serde_json::json!({ "title": "Delete?" }));
+    fake.assert_called_with(
+        "toast.show",
+        serde_json::json!({ "text": "Deleted", "durationMs": 3000 }),
+    );
+}